@@ -1,7 +1,22 @@
 // Re-export modules for use in integration tests and as a library
 pub mod lib {
     pub mod app;
+    pub mod cli;
+    pub mod clock;
+    pub mod config;
+    pub mod crash;
     pub mod db;
+    pub mod editor;
+    pub mod error;
+    pub mod export;
+    pub mod history;
+    pub mod import;
+    pub mod input;
+    pub mod keymap;
+    pub mod migrations;
+    pub mod parse;
+    pub mod search;
+    pub mod stats;
     pub mod throbber;
     pub mod ui;
 }
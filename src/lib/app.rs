@@ -1,27 +1,75 @@
+use crate::lib::clock::{Clock, SystemClock};
+use crate::lib::config::{Config, DeleteConfirmation};
 use crate::lib::db::Db;
+use crate::lib::error::{Error, Result};
+use crate::lib::history::{self, History, Step};
+use crate::lib::input::TextInput;
+use crate::lib::keymap::{Action, KeyContext};
+use crate::lib::parse;
+use crate::lib::search::Search;
+use crate::lib::stats::{Stats, StatsRange};
 use crate::lib::throbber::Throbber;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::widgets::TableState;
+use std::rc::Rc;
+use std::time::Instant;
 
 #[derive(Debug)]
 pub enum CurrentScreen {
     Main,
     Edit,
     Add,
+    AddPast,
+    Stats,
+    Trash,
+    /// Typing a search after `/`
+    Search,
+    ConfirmDelete,
     Exit,
 }
 
-#[derive(Debug)]
+impl CurrentScreen {
+    /// The section of the keymap that applies to this screen
+    pub fn key_context(&self) -> KeyContext {
+        match self {
+            CurrentScreen::Main => KeyContext::Main,
+            // The search bar is edited with the same keys as the dialogs
+            CurrentScreen::Edit
+            | CurrentScreen::Add
+            | CurrentScreen::AddPast
+            | CurrentScreen::Search => KeyContext::Dialog,
+            CurrentScreen::Stats => KeyContext::Stats,
+            CurrentScreen::Trash => KeyContext::Trash,
+            CurrentScreen::ConfirmDelete => KeyContext::Delete,
+            CurrentScreen::Exit => KeyContext::Exit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CurrentlyEditing {
     Name,
     Description,
+    Project,
+    Tags,
+    Date,
+    StartTime,
+    Duration,
 }
 
 #[derive(Debug)]
 pub struct App {
     pub timers: Vec<Timer>,
-    pub name_input: String,
-    pub description_input: String,
+    pub name_input: TextInput,
+    pub description_input: TextInput,
+    pub project_input: TextInput, // `Client / Project`, created on save if it doesn't exist
+    pub tags_input: TextInput,    // `#meeting #review`
+    pub date_input: TextInput,
+    pub start_time_input: TextInput, // empty keeps the current start time
+    pub duration_input: TextInput,   // empty keeps the current duration
+    pub input_error: Option<String>,
+    pub status: Option<Status>,
     pub currently_editing: Option<CurrentlyEditing>,
     pub current_screen: CurrentScreen,
     pub state: TableState,
@@ -29,54 +77,422 @@ pub struct App {
     pub db: Db,
     pub throbber: Throbber,
     pub exit_button_selected: bool, // true for Yes, false for No
+    pub config: Config,
+    pub projects: Vec<Project>,
+    pub project_filter: Option<usize>, // only show timers of this project
+    pub tag_filter: Option<String>,    // only show timers with this tag
+    pub search: Option<Search>,        // only show timers matching the search
+    pub search_input: TextInput,
+    pub stats: Option<Stats>, // loaded when the statistics screen is opened
+    pub delete_pressed_at: Option<Instant>, // first press of a double press delete
+    pub should_quit: bool,
+    pub clock: Rc<dyn Clock>,
+    pub trash: Vec<Timer>, // loaded when the trash is opened
+    pub trash_state: TableState,
+    pub history: History,
+    pub pending_editor: Option<usize>, // timer whose description the event loop opens in $EDITOR
+}
+
+/// A message in the status bar, shown until the next action
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Info(String),
+    Error(String),
 }
 
 impl App {
-    pub fn edit_timer(&mut self) {
-        if let Some(selected) = self.state.selected() {
-            if let Some(timer_index) = self.get_timer_index_from_selection(selected) {
-                self.timers[timer_index].name = self.name_input.clone();
-                self.timers[timer_index].description = self.description_input.clone();
-                self.db
-                    .edit_timer(
-                        &self.timers[timer_index],
-                        &self.name_input,
-                        &self.description_input,
-                    )
-                    .expect("Unable to edit timer");
-                self.currently_editing = None;
+    /// Fill the edit dialog with the selected timer
+    pub fn begin_edit(&mut self) {
+        if let Some(selected) = self.state.selected()
+            && let Some(timer_index) = self.get_timer_index_from_selection(selected)
+        {
+            self.current_screen = CurrentScreen::Edit;
+            self.currently_editing = Some(CurrentlyEditing::Name);
+            self.name_input.set(self.timers[timer_index].name.clone());
+            self.description_input
+                .set(self.timers[timer_index].description.clone());
+            self.project_input.set(
+                self.project_name(self.timers[timer_index].project_id)
+                    .unwrap_or_default(),
+            );
+            self.tags_input
+                .set(self.timers[timer_index].formatted_tags());
+            self.start_time_input.clear();
+            self.duration_input.clear();
+            self.input_error = None;
+        }
+    }
+
+    /// Apply the edit dialog to the selected timer. Start time and duration are
+    /// validated before anything is changed.
+    pub fn edit_timer(&mut self) -> Result<()> {
+        if let Some(selected) = self.state.selected()
+            && let Some(timer_index) = self.get_timer_index_from_selection(selected)
+        {
+            let timer = &self.timers[timer_index];
+            let now = self.clock.now();
+
+            let start_time = if self.start_time_input.value().trim().is_empty() {
+                None
+            } else {
+                let start_time =
+                    parse::parse_start_time(self.start_time_input.value(), timer.start_time)
+                        .map_err(Error::InvalidInput)?;
+                if start_time > now {
+                    return Err(Error::InvalidInput(
+                        "Start time cannot be in the future".to_string(),
+                    ));
+                }
+                Some(start_time)
+            };
+
+            let duration = if self.duration_input.value().trim().is_empty() {
+                None
+            } else {
+                let duration = parse::parse_duration_change(self.duration_input.value())
+                    .map_err(Error::InvalidInput)?
+                    .apply(timer.duration(now))
+                    .ok_or_else(|| Error::InvalidInput("Duration is too long".to_string()))?;
+                if duration < Duration::zero() {
+                    return Err(Error::InvalidInput(
+                        "Duration cannot be negative".to_string(),
+                    ));
+                }
+                Some(duration)
+            };
+
+            // Adjust a copy first so nothing changes if the result ends in
+            // the future, which a longer duration or later start can cause,
+            // or if a running session would be stretched back over earlier
+            // ones
+            let mut edited = timer.clone();
+            if let Some(start_time) = start_time {
+                edited.set_start_time(start_time);
+            }
+            if let Some(duration) = duration {
+                if edited
+                    .start_time
+                    .checked_add_signed(duration)
+                    .is_none_or(|end| end > now)
+                {
+                    return Err(Error::InvalidInput(
+                        "The timer cannot end in the future".to_string(),
+                    ));
+                }
+                edited.set_duration(duration, now);
+            }
+            if edited
+                .sessions
+                .iter()
+                .any(|session| session.ended_at.is_some_and(|ended_at| ended_at > now))
+            {
+                return Err(Error::InvalidInput(
+                    "The timer cannot end in the future".to_string(),
+                ));
+            }
+            if edited.sessions_overlap() {
+                return Err(Error::InvalidInput(
+                    "Not enough time since the previous session for this duration".to_string(),
+                ));
+            }
+
+            edited.project_id = self.resolve_project_input()?;
+            edited.name = self.name_input.value().to_string();
+            edited.description = self.description_input.value().to_string();
+            edited.tags = parse::parse_tags(self.tags_input.value());
+            self.db.edit_timer(
+                &mut edited,
+                self.name_input.value(),
+                self.description_input.value(),
+            )?;
+            let id = edited.id;
+            self.timers[timer_index] = edited;
+
+            // A new start time can move the timer to another date group
+            self.timers
+                .sort_by_key(|timer| (timer.start_time, timer.id));
+            self.update_selectable_rows();
+            if let Some(index) = self.timers.iter().position(|timer| timer.id == id) {
+                self.state
+                    .select(self.get_selection_from_timer_index(index));
             }
+            self.currently_editing = None;
+            self.input_error = None;
+        }
+        Ok(())
+    }
+
+    /// Ask the event loop to open the description of the selected timer in
+    /// the external editor
+    pub fn begin_edit_description(&mut self) {
+        self.pending_editor = self.selected_timer().map(|timer| timer.id);
+    }
+
+    /// The timer shown in the detail pane
+    pub fn selected_timer(&self) -> Option<&Timer> {
+        let timer_index = self.get_timer_index_from_selection(self.state.selected()?)?;
+        self.timers.get(timer_index)
+    }
+
+    /// Save a description written in the external editor. The change can be
+    /// undone like any other edit.
+    pub fn set_description(&mut self, id: usize, description: String) -> Result<()> {
+        let before = self.timers.clone();
+        if let Some(timer) = self.timers.iter_mut().find(|timer| timer.id == id) {
+            if timer.description == description {
+                return Ok(());
+            }
+            timer.description = description;
+            let (name, description) = (timer.name.clone(), timer.description.clone());
+            self.db.edit_timer(timer, &name, &description)?;
+        }
+        self.record_changes(Action::EditDescription.label(), &before)
+    }
+
+    /// Open an empty dialog for an entry of time that was already spent
+    pub fn begin_add_past(&mut self) {
+        self.current_screen = CurrentScreen::AddPast;
+        self.currently_editing = Some(CurrentlyEditing::Name);
+        self.clear_inputs();
+    }
+
+    /// Open an empty dialog for a new timer
+    pub fn begin_add(&mut self) {
+        self.current_screen = CurrentScreen::Add;
+        self.currently_editing = Some(CurrentlyEditing::Name);
+        self.clear_inputs();
+    }
+
+    /// Empty every dialog field, so nothing typed into an earlier dialog
+    /// ends up in the next timer
+    fn clear_inputs(&mut self) {
+        self.name_input.clear();
+        self.description_input.clear();
+        self.project_input.clear();
+        self.tags_input.clear();
+        self.date_input.clear();
+        self.start_time_input.clear();
+        self.duration_input.clear();
+        self.input_error = None;
+    }
+
+    /// Add a stopped timer for time that was already spent, from the past
+    /// entry dialog. Running timers are left alone.
+    pub fn add_past_timer(&mut self) -> Result<()> {
+        let (start_time, duration) = parse::parse_past_entry(
+            non_empty(self.date_input.value()),
+            non_empty(self.start_time_input.value()),
+            self.duration_input.value(),
+            self.clock.now(),
+        )
+        .map_err(Error::InvalidInput)?;
+
+        let mut timer = Timer::stopped(
+            self.name_input.value().to_string(),
+            self.description_input.value().to_string(),
+            start_time,
+            duration,
+        );
+        timer.project_id = self.resolve_project_input()?;
+        timer.tags = parse::parse_tags(self.tags_input.value());
+        self.db.add_timer_to_db(&mut timer)?;
+
+        // Keep the timers in chronological order so they land in the right date group
+        let index = self
+            .timers
+            .partition_point(|other| other.start_time <= timer.start_time);
+        self.timers.insert(index, timer);
+        if let Some(selected) = self.get_selection_from_timer_index(index) {
+            self.state.select(Some(selected));
+        }
+
+        self.clear_inputs();
+        self.currently_editing = None;
+        Ok(())
+    }
+
+    /// The fields of the dialog on the current screen, in tab order
+    pub fn editable_fields(&self) -> &'static [CurrentlyEditing] {
+        use CurrentlyEditing::*;
+        match self.current_screen {
+            CurrentScreen::Edit => &[Name, Description, Project, Tags, StartTime, Duration],
+            CurrentScreen::AddPast => {
+                &[Name, Description, Project, Tags, Date, StartTime, Duration]
+            }
+            _ => &[Name, Description, Project, Tags],
+        }
+    }
+
+    /// Whether the field being edited is the last one of the dialog
+    pub fn is_last_field(&self) -> bool {
+        self.currently_editing.as_ref() == self.editable_fields().last()
+    }
+
+    /// The input field that is currently being edited
+    pub fn current_input_mut(&mut self) -> Option<&mut TextInput> {
+        if let CurrentScreen::Search = self.current_screen {
+            return Some(&mut self.search_input);
+        }
+        let field = self.currently_editing?;
+        Some(self.input_mut(field))
+    }
+
+    /// The input of a dialog field
+    pub fn input_mut(&mut self, field: CurrentlyEditing) -> &mut TextInput {
+        match field {
+            CurrentlyEditing::Name => &mut self.name_input,
+            CurrentlyEditing::Description => &mut self.description_input,
+            CurrentlyEditing::Project => &mut self.project_input,
+            CurrentlyEditing::Tags => &mut self.tags_input,
+            CurrentlyEditing::Date => &mut self.date_input,
+            CurrentlyEditing::StartTime => &mut self.start_time_input,
+            CurrentlyEditing::Duration => &mut self.duration_input,
         }
     }
 }
 
-#[derive(Debug)]
+/// A duration as `HH:MM:SS`, where the hours can exceed 24
+pub fn format_duration(duration: Duration) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        duration.num_hours(),
+        duration.num_minutes() % 60,
+        duration.num_seconds() % 60
+    )
+}
+
+/// Modifiers that turn a character key into a shortcut instead of typing it
+fn typing_modifiers() -> KeyModifiers {
+    KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SUPER
+}
+
+/// The timer to select after undoing or redoing a step
+fn first_id(step: &Step) -> Option<usize> {
+    step.changes.first().map(|change| change.id)
+}
+
+fn non_empty(input: &str) -> Option<&str> {
+    Some(input.trim()).filter(|input| !input.is_empty())
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Timer {
     pub start_time: DateTime<Utc>,
     pub name: String,
-    pub(crate) duration: Duration,
+    pub sessions: Vec<Session>,
     pub description: String,
     pub id: usize,
     pub running: bool,
+    pub project_id: Option<usize>,
+    pub tags: Vec<String>,                 // sorted, without the leading `#`
+    pub deleted_at: Option<DateTime<Utc>>, // when the timer was moved to the trash
+}
+
+/// A project timers can be booked on, optionally belonging to a client
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub id: usize,
+    pub name: String,
+    pub client: Option<String>,
+}
+
+/// A single start/stop stretch of work on a timer. `ended_at` is `None`
+/// while the session is still running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: usize,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
 }
 
 impl App {
-    /// Create a new App instance using the platform-appropriate database path
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let db = Db::new_with_default_path()?;
+    /// Create a new App instance using the configured database
+    pub fn new(config: Config) -> Result<Self> {
+        let db = config.open_db()?;
+
+        let mut app = App::with_db(db);
+        app.config = config;
+        Ok(app)
+    }
+
+    /// Create a new App instance backed by the given database
+    pub fn with_db(db: Db) -> Self {
+        App::with_clock(db, Rc::new(SystemClock))
+    }
 
-        Ok(App {
+    /// Create a new App instance that reads the time from `clock`
+    pub fn with_clock(db: Db, clock: Rc<dyn Clock>) -> Self {
+        App {
             state: TableState::default().with_selected(1),
             timers: Vec::new(),
             current_screen: CurrentScreen::Main,
-            name_input: String::new(),
-            description_input: String::new(),
+            name_input: TextInput::default(),
+            description_input: TextInput::default(),
+            project_input: TextInput::default(),
+            tags_input: TextInput::default(),
+            date_input: TextInput::default(),
+            start_time_input: TextInput::default(),
+            duration_input: TextInput::default(),
+            input_error: None,
+            status: None,
             currently_editing: None,
             selectable_rows: Vec::new(),
             db,
             throbber: Throbber::new(),
             exit_button_selected: false,
-        })
+            config: Config::default(),
+            projects: Vec::new(),
+            project_filter: None,
+            tag_filter: None,
+            search: None,
+            search_input: TextInput::default(),
+            stats: None,
+            delete_pressed_at: None,
+            should_quit: false,
+            clock,
+            trash: Vec::new(),
+            trash_state: TableState::default(),
+            history: History::default(),
+            pending_editor: None,
+        }
+    }
+
+    /// Load the timers and projects from the database, purging timers that
+    /// have been in the trash for longer than the retention period
+    pub fn load(&mut self) -> Result<()> {
+        if self.config.trash_retention_days > 0 {
+            let cutoff = self.clock.now() - Duration::days(self.config.trash_retention_days as i64);
+            let purged = self.db.purge_deleted_before(cutoff)?;
+            self.history.forget(&purged);
+        }
+        self.timers = self.db.get_timers_from_db()?;
+        self.projects = self.db.get_projects()?;
+        Ok(())
+    }
+
+    /// Show an error in the status bar
+    pub fn report_error(&mut self, error: Error) {
+        self.status = Some(Status::Error(error.to_string()));
+    }
+
+    /// A short description of the app's state for crash reports. Timer names
+    /// and descriptions are left out.
+    pub fn summary(&self) -> String {
+        format!(
+            "screen: {:?}\nediting: {:?}\ntimers: {} ({} running)\nselected row: {:?} of {}\nproject filter: {:?}\ntag filter: {:?}\nstatus: {:?}",
+            self.current_screen,
+            self.currently_editing,
+            self.timers.len(),
+            self.timers.iter().filter(|timer| timer.running).count(),
+            self.state.selected(),
+            self.selectable_rows.len(),
+            self.project_filter,
+            self.tag_filter,
+            match &self.status {
+                Some(Status::Error(message)) => Some(message),
+                _ => None,
+            }
+        )
     }
 
     pub fn next_row(&mut self) {
@@ -115,59 +531,685 @@ impl App {
         self.state.select(Some(prev));
     }
 
-    pub fn add_timer(&mut self) {
-        let mut timer = Timer::new(self.name_input.clone(), self.description_input.clone());
-        match self.timers.last_mut() {
-            Some(t) => t.stop(),
-            None => (),
+    pub fn add_timer(&mut self) -> Result<()> {
+        let mut timer = Timer::new(
+            self.name_input.value().to_string(),
+            self.description_input.value().to_string(),
+            self.clock.now(),
+        );
+        timer.project_id = self.resolve_project_input()?;
+        timer.tags = parse::parse_tags(self.tags_input.value());
+        if !self.config.allow_multiple_running {
+            self.stop_running_timers(None)?;
         }
-        self.db
-            .add_timer_to_db(&mut timer)
-            .expect("TODO: panic message");
+        self.db.add_timer_to_db(&mut timer)?;
         self.timers.push(timer);
-        self.name_input = String::new();
-        self.description_input = String::new();
+        self.clear_inputs();
+        Ok(())
+    }
+
+    /// The display name of a project, if the timer has one
+    pub fn project_name(&self, project_id: Option<usize>) -> Option<String> {
+        let project_id = project_id?;
+        self.projects
+            .iter()
+            .find(|project| project.id == project_id)
+            .map(Project::display_name)
+    }
+
+    /// Find or create the project typed into the dialog
+    fn resolve_project_input(&mut self) -> Result<Option<usize>> {
+        let project_id = self.db.find_or_create_project(self.project_input.value())?;
+        self.projects = self.db.get_projects()?;
+        Ok(project_id)
+    }
+
+    /// Pick the next (or previous) existing project in the project field.
+    /// Cycling past either end clears the field.
+    pub fn cycle_project_input(&mut self, forward: bool) {
+        let names: Vec<String> = self.projects.iter().map(Project::display_name).collect();
+        let current = names
+            .iter()
+            .position(|name| *name == self.project_input.value().trim());
+        let next = match (current, forward) {
+            (None, true) => Some(0),
+            (None, false) => names.len().checked_sub(1),
+            (Some(index), true) => Some(index + 1).filter(|&next| next < names.len()),
+            (Some(index), false) => index.checked_sub(1),
+        };
+        self.project_input
+            .set(next.map(|index| names[index].clone()).unwrap_or_default());
+    }
+
+    /// Cycle the main screen through showing all timers and only the timers of
+    /// each project
+    pub fn cycle_project_filter(&mut self) {
+        let position = self
+            .projects
+            .iter()
+            .position(|project| Some(project.id) == self.project_filter);
+        self.project_filter = match position {
+            None => self.projects.first().map(|project| project.id),
+            Some(index) => self.projects.get(index + 1).map(|project| project.id),
+        };
+        self.state.select(Some(1));
     }
 
-    pub fn delete_selected_timer(&mut self) -> Result<(), rusqlite::Error> {
-        if let Some(selected) = self.state.selected() {
-            if let Some(timer_index) = self.get_timer_index_from_selection(selected) {
-                self.db.delete_timer(self.timers[timer_index].id)?;
-                self.timers.remove(timer_index);
+    /// Show the statistics screen for the last week
+    pub fn open_stats(&mut self) -> Result<()> {
+        self.load_stats(StatsRange::Week)?;
+        self.current_screen = CurrentScreen::Stats;
+        Ok(())
+    }
+
+    /// Switch the statistics screen between the last 7 and 30 days
+    pub fn toggle_stats_range(&mut self) -> Result<()> {
+        let range = self
+            .stats
+            .as_ref()
+            .map_or(StatsRange::Week, |stats| stats.range);
+        self.load_stats(range.toggle())
+    }
+
+    /// Reload the statistics, so running timers are counted up to now
+    pub fn refresh_stats(&mut self) -> Result<()> {
+        match self.stats.as_ref().map(|stats| stats.range) {
+            Some(range) => self.load_stats(range),
+            None => Ok(()),
+        }
+    }
+
+    fn load_stats(&mut self, range: StatsRange) -> Result<()> {
+        self.stats = Some(Stats::load(&self.db, range, self.clock.now())?);
+        Ok(())
+    }
+
+    /// All tags in use, sorted by name
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .timers
+            .iter()
+            .flat_map(|timer| timer.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Cycle the main screen through showing all timers and only the timers
+    /// with each tag
+    pub fn cycle_tag_filter(&mut self) {
+        let tags = self.all_tags();
+        let position = tags
+            .iter()
+            .position(|tag| Some(tag) == self.tag_filter.as_ref());
+        self.tag_filter = match position {
+            None => tags.first().cloned(),
+            Some(index) => tags.get(index + 1).cloned(),
+        };
+        self.state.select(Some(1));
+    }
+
+    /// Indices of the timers shown on the main screen, in display order
+    pub fn visible_timer_indices(&self) -> Vec<usize> {
+        self.timers
+            .iter()
+            .enumerate()
+            .filter(|(_, timer)| {
+                self.project_filter
+                    .is_none_or(|project_id| timer.project_id == Some(project_id))
+                    && self
+                        .tag_filter
+                        .as_ref()
+                        .is_none_or(|tag| timer.tags.contains(tag))
+                    && self
+                        .search
+                        .as_ref()
+                        .is_none_or(|search| search.is_match(timer))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Continue working on the selected timer by starting a new timer with
+    /// the same name and description, grouped under today's date
+    pub fn continue_timer(&mut self) -> Result<()> {
+        if let Some(selected) = self.state.selected()
+            && let Some(timer_index) = self.get_timer_index_from_selection(selected)
+        {
+            let mut timer = Timer::new(
+                self.timers[timer_index].name.clone(),
+                self.timers[timer_index].description.clone(),
+                self.clock.now(),
+            );
+            timer.project_id = self.timers[timer_index].project_id;
+            timer.tags = self.timers[timer_index].tags.clone();
+            if !self.config.allow_multiple_running {
+                self.stop_running_timers(None)?;
+            }
+            self.db.add_timer_to_db(&mut timer)?;
+            self.timers.push(timer);
+            if let Some(selected) = self.get_selection_from_timer_index(self.timers.len() - 1) {
+                self.state.select(Some(selected));
             }
         }
         Ok(())
     }
 
+    /// Ask for confirmation before deleting the selected timer
+    pub fn begin_delete(&mut self) {
+        if let Some(selected) = self.state.selected()
+            && self.get_timer_index_from_selection(selected).is_some()
+        {
+            self.current_screen = CurrentScreen::ConfirmDelete;
+        }
+    }
+
+    /// Move the selected timer to the trash
+    pub fn delete_selected_timer(&mut self) -> Result<()> {
+        if let Some(selected) = self.state.selected()
+            && let Some(timer_index) = self.get_timer_index_from_selection(selected)
+        {
+            self.db
+                .delete_timer(self.timers[timer_index].id, self.clock.now())?;
+            let timer = self.timers.remove(timer_index);
+            let undo_key = self
+                .config
+                .keys
+                .key(KeyContext::Main, Action::Undo)
+                .map_or(String::new(), |key| format!(", {} to undo", key));
+            self.status = Some(Status::Info(format!(
+                "Moved '{}' to the trash{}",
+                timer.name, undo_key
+            )));
+        }
+        Ok(())
+    }
+
+    /// Revert the last change to the timers
+    pub fn undo(&mut self) -> Result<()> {
+        let step = self
+            .history
+            .undo(&self.db)?
+            .map(|step| (step.label, first_id(step)));
+        self.show_history_step("Undo", step)
+    }
+
+    /// Apply the last undone change again
+    pub fn redo(&mut self) -> Result<()> {
+        let step = self
+            .history
+            .redo(&self.db)?
+            .map(|step| (step.label, first_id(step)));
+        self.show_history_step("Redo", step)
+    }
+
+    /// Reload the timers after an undo or redo and select the changed one
+    fn show_history_step(&mut self, verb: &str, step: Option<(&str, Option<usize>)>) -> Result<()> {
+        let Some((label, id)) = step else {
+            self.status = Some(Status::Info(format!("Nothing to {}", verb.to_lowercase())));
+            return Ok(());
+        };
+        self.timers = self.db.get_timers_from_db()?;
+        if let Some(id) = id {
+            self.select_timer(id);
+        }
+        self.status = Some(Status::Info(format!("{}: {}", verb, label)));
+        Ok(())
+    }
+
+    /// Select a timer on the main screen if it is shown
+    fn select_timer(&mut self, id: usize) {
+        if let Some(index) = self.timers.iter().position(|timer| timer.id == id)
+            && let Some(selected) = self.get_selection_from_timer_index(index)
+        {
+            self.state.select(Some(selected));
+        }
+    }
+
+    /// Take a timer out of the trash and select it on the main screen
+    fn restore_timer(&mut self, id: usize) -> Result<()> {
+        self.db.restore_timer(id)?;
+        self.timers = self.db.get_timers_from_db()?;
+        self.select_timer(id);
+        Ok(())
+    }
+
+    /// Show the timers in the trash
+    pub fn open_trash(&mut self) -> Result<()> {
+        self.trash = self.db.get_deleted_timers()?;
+        self.trash_state
+            .select((!self.trash.is_empty()).then_some(0));
+        self.current_screen = CurrentScreen::Trash;
+        Ok(())
+    }
+
+    /// Restore the timer selected in the trash
+    pub fn restore_from_trash(&mut self) -> Result<()> {
+        if let Some(index) = self.trash_state.selected()
+            && index < self.trash.len()
+        {
+            let timer = self.trash.remove(index);
+            self.restore_timer(timer.id)?;
+            self.select_in_trash(index);
+        }
+        Ok(())
+    }
+
+    /// Delete the timer selected in the trash for good
+    pub fn purge_from_trash(&mut self) -> Result<()> {
+        if let Some(index) = self.trash_state.selected()
+            && index < self.trash.len()
+        {
+            let id = self.trash[index].id;
+            self.db.purge_timer(id)?;
+            self.history.forget(&[id]);
+            self.trash.remove(index);
+            self.select_in_trash(index);
+        }
+        Ok(())
+    }
+
+    /// Keep the selection in the trash on the same row, or the last one
+    fn select_in_trash(&mut self, index: usize) {
+        let selected = match self.trash.len() {
+            0 => None,
+            len => Some(index.min(len - 1)),
+        };
+        self.trash_state.select(selected);
+    }
+
     pub fn toggle_editing(&mut self) {
+        let fields = self.editable_fields();
         if let Some(edit_mode) = &self.currently_editing {
-            match edit_mode {
-                CurrentlyEditing::Name => {
-                    self.currently_editing = Some(CurrentlyEditing::Description)
-                }
-                CurrentlyEditing::Description => {
-                    self.currently_editing = Some(CurrentlyEditing::Name)
-                }
-            }
+            let position = fields.iter().position(|field| field == edit_mode);
+            let next = position.map_or(0, |position| (position + 1) % fields.len());
+            self.currently_editing = Some(fields[next]);
         } else {
             self.currently_editing = Some(CurrentlyEditing::Name);
         }
     }
 
-    pub fn toggle_timer(&mut self) {
-        if let Some(timer) = self.timers.last_mut() {
-            if timer.running {
-                timer.running = false;
+    /// Start or stop the selected timer. Unless several timers may run at
+    /// once, starting a timer stops every other running timer.
+    pub fn toggle_timer(&mut self) -> Result<()> {
+        if let Some(selected) = self.state.selected()
+            && let Some(timer_index) = self.get_timer_index_from_selection(selected)
+        {
+            let now = self.clock.now();
+            if self.timers[timer_index].running {
+                self.timers[timer_index].stop(now);
             } else {
-                timer.running = true;
+                if !self.config.allow_multiple_running {
+                    self.stop_running_timers(Some(timer_index))?;
+                }
+                self.timers[timer_index].start(now);
             }
+            self.db
+                .update_timers_in_db(std::slice::from_mut(&mut self.timers[timer_index]))?;
         }
+        Ok(())
+    }
+
+    /// Stop all running timers except the one at index `except`
+    fn stop_running_timers(&mut self, except: Option<usize>) -> Result<()> {
+        let now = self.clock.now();
+        for (index, timer) in self.timers.iter_mut().enumerate() {
+            if timer.running && Some(index) != except {
+                timer.stop(now);
+                self.db.update_timers_in_db(std::slice::from_mut(timer))?;
+            }
+        }
+        Ok(())
     }
 
     pub fn toggle_exit_button(&mut self) {
         self.exit_button_selected = !self.exit_button_selected;
     }
 
+    /// Resolve a key press to an action with the keymap of the current screen.
+    /// With double press deletes only the second press within the configured
+    /// window resolves to [`Action::DeleteTimer`]. Unbound characters typed
+    /// into a dialog resolve to [`Action::Type`].
+    pub fn action_for_key(&mut self, key: &KeyEvent) -> Option<Action> {
+        let context = self.current_screen.key_context();
+        if key.kind == KeyEventKind::Release
+            || (context == KeyContext::Dialog && key.kind != KeyEventKind::Press)
+        {
+            return None;
+        }
+
+        let action = match self.config.keys.action(context, key) {
+            None if context == KeyContext::Dialog => match key.code {
+                KeyCode::Char(c) if !key.modifiers.intersects(typing_modifiers()) => {
+                    Some(Action::Type(c))
+                }
+                _ => None,
+            },
+            action => action,
+        };
+
+        // Any other key press resets a pending delete
+        let now = self.clock.instant();
+        let pressed_at = self.delete_pressed_at.take();
+        if action == Some(Action::DeleteTimer)
+            && self.config.delete_confirmation == DeleteConfirmation::DoublePress
+        {
+            let window = std::time::Duration::from_millis(self.config.delete_window_ms);
+            match pressed_at {
+                Some(pressed_at) if now.duration_since(pressed_at) < window => {}
+                Some(_) => return None,
+                None => {
+                    self.delete_pressed_at = Some(now);
+                    return None;
+                }
+            }
+        }
+        action
+    }
+
+    /// Resolve a key press and apply the resulting action
+    pub fn handle_key(&mut self, key: &KeyEvent) {
+        if let Some(action) = self.action_for_key(key) {
+            self.apply(action);
+        }
+    }
+
+    /// Apply an action to the current screen. Actions that don't belong to
+    /// the current screen are ignored. Errors are shown in the status bar
+    /// until the next action.
+    pub fn apply(&mut self, action: Action) {
+        self.status = None;
+        let label = self.undo_label(action);
+        let before = label.map(|_| self.timers.clone());
+        if let Err(e) = self.reduce(action) {
+            self.report_error(e);
+        }
+        if let (Some(label), Some(before)) = (label, before)
+            && let Err(e) = self.record_changes(label, &before)
+        {
+            self.report_error(e);
+        }
+    }
+
+    /// What to call the change an action makes to the timers, or `None` for
+    /// actions that can't be undone
+    fn undo_label(&self, action: Action) -> Option<&'static str> {
+        let action = match (&self.current_screen, action) {
+            (
+                CurrentScreen::Main,
+                Action::DeleteTimer | Action::ToggleTimer | Action::ContinueTimer,
+            ) => action,
+            (CurrentScreen::ConfirmDelete, Action::Confirm) => Action::DeleteTimer,
+            (CurrentScreen::Add, Action::Submit) => Action::AddTimer,
+            (CurrentScreen::Edit, Action::Submit) => Action::EditTimer,
+            (CurrentScreen::AddPast, Action::Submit) => Action::AddPastEntry,
+            _ => return None,
+        };
+        Some(action.label())
+    }
+
+    /// Put the timers that changed since `before` on the undo stack
+    fn record_changes(&mut self, label: &'static str, before: &[Timer]) -> Result<()> {
+        let changes = history::diff(before, &self.timers, &self.db)?;
+        self.history.record(Step { label, changes });
+        Ok(())
+    }
+
+    fn reduce(&mut self, action: Action) -> Result<()> {
+        match self.current_screen {
+            CurrentScreen::Main => match action {
+                Action::Quit => self.current_screen = CurrentScreen::Exit,
+                Action::NextRow => self.next_row(),
+                Action::PreviousRow => self.previous_row(),
+                Action::DeleteTimer if !self.timers.is_empty() => {
+                    match self.config.delete_confirmation {
+                        DeleteConfirmation::Dialog => self.begin_delete(),
+                        DeleteConfirmation::DoublePress | DeleteConfirmation::None => {
+                            self.delete_selected_timer()?
+                        }
+                    }
+                }
+                Action::AddTimer => self.begin_add(),
+                Action::EditTimer if !self.timers.is_empty() => self.begin_edit(),
+                Action::EditDescription => self.begin_edit_description(),
+                Action::ToggleTimer => self.toggle_timer()?,
+                Action::ContinueTimer => self.continue_timer()?,
+                Action::AddPastEntry => self.begin_add_past(),
+                Action::FilterProject => self.cycle_project_filter(),
+                Action::FilterTag => self.cycle_tag_filter(),
+                Action::ShowStats => self.open_stats()?,
+                Action::Undo => self.undo()?,
+                Action::Redo => self.redo()?,
+                Action::ShowTrash => self.open_trash()?,
+                Action::Search => self.begin_search(),
+                Action::NextMatch => self.jump_to_match(true),
+                Action::PreviousMatch => self.jump_to_match(false),
+                _ => {}
+            },
+            CurrentScreen::ConfirmDelete => match action {
+                Action::Confirm => {
+                    self.current_screen = CurrentScreen::Main;
+                    self.delete_selected_timer()?;
+                }
+                Action::Deny => self.current_screen = CurrentScreen::Main,
+                _ => {}
+            },
+            CurrentScreen::Stats => match action {
+                Action::Cancel => self.current_screen = CurrentScreen::Main,
+                Action::ToggleRange => self.toggle_stats_range()?,
+                _ => {}
+            },
+            CurrentScreen::Trash => match action {
+                Action::NextRow => self.trash_state.select_next(),
+                Action::PreviousRow => self.trash_state.select_previous(),
+                Action::RestoreTimer => self.restore_from_trash()?,
+                Action::PurgeTimer => self.purge_from_trash()?,
+                Action::Cancel => self.current_screen = CurrentScreen::Main,
+                _ => {}
+            },
+            CurrentScreen::Exit => match action {
+                Action::Deny => self.current_screen = CurrentScreen::Main,
+                Action::Confirm => self.should_quit = true,
+                Action::ToggleButton => self.toggle_exit_button(),
+                Action::PressButton => {
+                    if self.exit_button_selected {
+                        self.should_quit = true; // Yes selected - exit
+                    } else {
+                        self.current_screen = CurrentScreen::Main; // No selected - go back
+                    }
+                }
+                _ => {}
+            },
+            CurrentScreen::Add | CurrentScreen::Edit | CurrentScreen::AddPast => match action {
+                Action::Submit => {
+                    if self.is_last_field() {
+                        self.submit_dialog()?;
+                    } else {
+                        self.toggle_editing();
+                    }
+                }
+                Action::Cancel => {
+                    self.current_screen = CurrentScreen::Main;
+                    self.currently_editing = None;
+                    self.input_error = None;
+                }
+                Action::NextField => self.toggle_editing(),
+                Action::NextOption | Action::PreviousOption
+                    if self.currently_editing == Some(CurrentlyEditing::Project) =>
+                {
+                    self.cycle_project_input(action == Action::NextOption);
+                }
+                _ => {
+                    self.edit_text(action);
+                }
+            },
+            CurrentScreen::Search => match action {
+                Action::Submit => self.submit_search(),
+                Action::Cancel => {
+                    self.current_screen = CurrentScreen::Main;
+                    self.set_search(None);
+                }
+                _ => {
+                    if self.edit_text(action) {
+                        self.update_search();
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Apply a text editing action to the field that is currently being
+    /// edited. Returns false for actions that don't edit text.
+    fn edit_text(&mut self, action: Action) -> bool {
+        let edit: fn(&mut TextInput) = match action {
+            Action::DeleteChar => TextInput::delete_before,
+            Action::DeleteNextChar => TextInput::delete_after,
+            Action::DeleteWord => TextInput::delete_word_before,
+            Action::DeleteToStart => TextInput::delete_to_start,
+            Action::CursorLeft => TextInput::move_left,
+            Action::CursorRight => TextInput::move_right,
+            Action::WordLeft => TextInput::move_word_left,
+            Action::WordRight => TextInput::move_word_right,
+            Action::CursorHome => TextInput::move_home,
+            Action::CursorEnd => TextInput::move_end,
+            Action::Type(c) => {
+                self.edit_input(|input| input.insert(c));
+                return true;
+            }
+            _ => return false,
+        };
+        self.edit_input(edit);
+        true
+    }
+
+    /// Apply an edit to the field that is currently being edited
+    fn edit_input(&mut self, edit: impl FnOnce(&mut TextInput)) {
+        if let Some(input) = self.current_input_mut() {
+            edit(input);
+        }
+    }
+
+    /// Insert pasted text into the field that is currently being edited.
+    /// Pastes outside of a dialog are ignored.
+    pub fn paste(&mut self, text: &str) {
+        if self.current_screen.key_context() == KeyContext::Dialog {
+            self.status = None;
+            self.edit_input(|input| input.insert_str(text));
+            if let CurrentScreen::Search = self.current_screen {
+                self.update_search();
+            }
+        }
+    }
+
+    /// Open the search bar with an empty query, like `/` in vim
+    pub fn begin_search(&mut self) {
+        self.current_screen = CurrentScreen::Search;
+        self.search_input.clear();
+        self.set_search(None);
+    }
+
+    /// Filter the timers while the query is typed
+    fn update_search(&mut self) {
+        self.set_search(Search::new(self.search_input.value()));
+    }
+
+    /// Close the search bar and keep the timers filtered
+    fn submit_search(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+        if let Some(search) = &self.search
+            && self.visible_timer_indices().is_empty()
+        {
+            self.status = Some(Status::Info(format!(
+                "No timers match '{}'",
+                search.query()
+            )));
+        }
+    }
+
+    /// Change the search and keep the selected timer selected if it still
+    /// matches, otherwise select the first match
+    fn set_search(&mut self, search: Option<Search>) {
+        let selected = self.selected_timer().map(|timer| timer.id);
+        self.search = search;
+        self.update_selectable_rows();
+
+        let visible = self.visible_timer_indices();
+        let index = selected
+            .and_then(|id| self.timers.iter().position(|timer| timer.id == id))
+            .filter(|index| visible.contains(index))
+            .or_else(|| visible.first().copied());
+        let selection = index.and_then(|index| self.get_selection_from_timer_index(index));
+        self.state.select(selection);
+    }
+
+    /// Jump to the next (or previous) timer matching the search
+    pub fn jump_to_match(&mut self, forward: bool) {
+        let Some(search) = &self.search else {
+            let key = self.config.keys.key(KeyContext::Main, Action::Search);
+            self.status = Some(Status::Info(match key {
+                Some(key) => format!("No search, press {} to search", key),
+                None => "No search".to_string(),
+            }));
+            return;
+        };
+        let query = search.query().to_string();
+        if forward {
+            self.next_row();
+        } else {
+            self.previous_row();
+        }
+
+        let visible = self.visible_timer_indices();
+        let position = self
+            .state
+            .selected()
+            .and_then(|selected| self.get_timer_index_from_selection(selected))
+            .and_then(|index| visible.iter().position(|&visible| visible == index));
+        if let Some(position) = position {
+            self.status = Some(Status::Info(format!(
+                "/{} - match {} of {}",
+                query,
+                position + 1,
+                visible.len()
+            )));
+        }
+    }
+
+    /// Recompute which table rows are timers for the timers that are shown.
+    /// Each day starts with a date row, like the table drawn by the UI.
+    pub fn update_selectable_rows(&mut self) {
+        let visible = self.visible_timer_indices();
+        self.selectable_rows = visible
+            .iter()
+            .enumerate()
+            .flat_map(|(position, &index)| {
+                let new_day = position == 0
+                    || self.timers[index].local_date()
+                        != self.timers[visible[position - 1]].local_date();
+                new_day.then_some(false).into_iter().chain([true])
+            })
+            .collect();
+    }
+
+    /// Save the add, edit or past entry dialog, staying in it if the input is
+    /// invalid
+    fn submit_dialog(&mut self) -> Result<()> {
+        let result = match self.current_screen {
+            CurrentScreen::Add => self.add_timer(),
+            CurrentScreen::Edit => self.edit_timer(),
+            CurrentScreen::AddPast => self.add_past_timer(),
+            _ => return Ok(()),
+        };
+        match result {
+            Ok(()) => self.current_screen = CurrentScreen::Main,
+            Err(Error::InvalidInput(message)) => self.input_error = Some(message),
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
     /// Convert table selection index to timer index, accounting for non-selectable date rows
     pub fn get_timer_index_from_selection(&self, selected_index: usize) -> Option<usize> {
         if selected_index >= self.selectable_rows.len() || !self.selectable_rows[selected_index] {
@@ -175,52 +1217,223 @@ impl App {
         }
 
         // Count how many selectable rows come before the selected index
-        let timer_index = self.selectable_rows[..selected_index]
+        let visible_index = self.selectable_rows[..selected_index]
             .iter()
             .filter(|&&is_selectable| is_selectable)
             .count();
 
-        if timer_index < self.timers.len() {
-            Some(timer_index)
-        } else {
-            None
-        }
+        self.visible_timer_indices().get(visible_index).copied()
+    }
+
+    /// Convert timer index to table selection index, accounting for the date rows
+    /// preceding it. Returns `None` if the timer is hidden by a filter.
+    pub fn get_selection_from_timer_index(&self, timer_index: usize) -> Option<usize> {
+        let visible = self.visible_timer_indices();
+        let visible_index = visible.iter().position(|&index| index == timer_index)?;
+        let date_rows = visible[..=visible_index]
+            .iter()
+            .enumerate()
+            .filter(|&(i, &index)| {
+                i == 0
+                    || self.timers[index].local_date() != self.timers[visible[i - 1]].local_date()
+            })
+            .count();
+
+        Some(visible_index + date_rows)
     }
 }
 
 impl Timer {
-    pub fn new(name: String, description: String) -> Timer {
+    /// Create a timer that starts running at `now`
+    pub fn new(name: String, description: String, now: DateTime<Utc>) -> Timer {
         Timer {
-            start_time: Utc::now(),
-            duration: Duration::zero(),
+            start_time: now,
+            sessions: vec![Session::new(now)],
             name,
             description,
             id: 0,
             running: true,
+            project_id: None,
+            tags: Vec::new(),
+            deleted_at: None,
+        }
+    }
+
+    /// Create a stopped timer for time that has already been spent
+    pub fn stopped(
+        name: String,
+        description: String,
+        start_time: DateTime<Utc>,
+        duration: Duration,
+    ) -> Timer {
+        Timer {
+            start_time,
+            sessions: vec![Session {
+                id: 0,
+                started_at: start_time,
+                ended_at: Some(start_time + duration),
+            }],
+            name,
+            description,
+            id: 0,
+            running: false,
+            project_id: None,
+            tags: Vec::new(),
+            deleted_at: None,
         }
     }
 
-    pub fn stop(&mut self) {
+    /// Stop the timer, closing its open session at `now`
+    pub fn stop(&mut self, now: DateTime<Utc>) {
         self.running = false;
+        for session in self.sessions.iter_mut().filter(|s| s.ended_at.is_none()) {
+            session.ended_at = Some(now);
+        }
     }
 
-    pub fn start(&mut self) {
-        self.running = true;
+    /// Start the timer again, opening a new session at `now`
+    pub fn start(&mut self, now: DateTime<Utc>) {
+        if !self.running {
+            self.running = true;
+            self.sessions.push(Session::new(now));
+        }
     }
-    pub fn tick(&mut self) {
-        self.duration += Duration::seconds(1);
+
+    /// Move the timer and all of its sessions so that it starts at `start_time`
+    pub fn set_start_time(&mut self, start_time: DateTime<Utc>) {
+        let delta = start_time - self.start_time;
+        self.start_time = start_time;
+        for session in self.sessions.iter_mut() {
+            session.started_at += delta;
+            if let Some(ended_at) = session.ended_at.as_mut() {
+                *ended_at += delta;
+            }
+        }
     }
 
-    pub fn formatted_duration(&self) -> String {
-        format!(
-            "{:02}:{:02}:{:02}",
-            self.duration.num_hours(),
-            self.duration.num_minutes() % 60,
-            self.duration.num_seconds() % 60
-        )
+    /// Lengthen or shorten the most recent sessions so that the total tracked
+    /// time at `now` becomes `duration`
+    pub fn set_duration(&mut self, duration: Duration, now: DateTime<Utc>) {
+        let delta = duration - self.duration(now);
+
+        if delta >= Duration::zero() {
+            match self.sessions.last_mut() {
+                Some(session) => session.lengthen(delta),
+                None => self.sessions.push(Session {
+                    id: 0,
+                    started_at: self.start_time,
+                    ended_at: Some(self.start_time + delta),
+                }),
+            }
+            return;
+        }
+
+        let mut remaining = -delta;
+        for session in self.sessions.iter_mut().rev() {
+            let cut = remaining.min(session.duration(now));
+            session.lengthen(-cut);
+            remaining -= cut;
+            if remaining <= Duration::zero() {
+                break;
+            }
+        }
+    }
+
+    /// Whether a session starts before the timer or before the previous
+    /// session ended, which lengthening a running timer can cause
+    pub fn sessions_overlap(&self) -> bool {
+        let starts_early = self
+            .sessions
+            .first()
+            .is_some_and(|session| session.started_at < self.start_time);
+        starts_early
+            || self.sessions.windows(2).any(|pair| {
+                pair[0]
+                    .ended_at
+                    .is_none_or(|ended_at| ended_at > pair[1].started_at)
+            })
+    }
+
+    /// Total time tracked, summed over all sessions. A running session counts
+    /// up to `now`.
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.sessions
+            .iter()
+            .fold(Duration::zero(), |total, session| {
+                total + session.duration(now)
+            })
+    }
+
+    pub fn formatted_duration(&self, now: DateTime<Utc>) -> String {
+        format_duration(self.duration(now))
+    }
+
+    /// The local day the timer started on, which it is listed under
+    pub fn local_date(&self) -> NaiveDate {
+        parse::local_date(self.start_time)
     }
 
     pub fn formatted_date(&self) -> String {
-        self.start_time.format("%d-%m-%Y").to_string()
+        self.start_time
+            .with_timezone(&Local)
+            .format("%d-%m-%Y")
+            .to_string()
+    }
+
+    /// Tags as they are typed into the dialogs, e.g. `#meeting #review`
+    pub fn formatted_tags(&self) -> String {
+        self.tags
+            .iter()
+            .map(|tag| format!("#{}", tag))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Session {
+    pub fn new(started_at: DateTime<Utc>) -> Session {
+        Session {
+            id: 0,
+            started_at,
+            ended_at: None,
+        }
+    }
+
+    /// Length of the session, counting up to `now` while it is running
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.ended_at.unwrap_or(now) - self.started_at
+    }
+
+    /// Change the length of the session by `delta`. A running session keeps
+    /// running, so its start is moved instead of its end.
+    pub fn lengthen(&mut self, delta: Duration) {
+        match self.ended_at.as_mut() {
+            Some(ended_at) => *ended_at += delta,
+            None => self.started_at -= delta,
+        }
+    }
+}
+
+impl Project {
+    /// The name shown in the table and typed into dialogs, `Client / Project`
+    pub fn display_name(&self) -> String {
+        match &self.client {
+            Some(client) => format!("{} / {}", client, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Split user input of the form `Client / Project` or `Project` into the
+    /// client and project name. Returns `None` for empty input.
+    pub fn parse_display_name(input: &str) -> Option<(Option<&str>, &str)> {
+        let (client, name) = match input.split_once('/') {
+            Some((client, name)) => (Some(client.trim()).filter(|c| !c.is_empty()), name.trim()),
+            None => (None, input.trim()),
+        };
+        if name.is_empty() {
+            None
+        } else {
+            Some((client, name))
+        }
     }
 }
@@ -0,0 +1,301 @@
+use crate::lib::app::Timer;
+use crate::lib::clock::Clock;
+use crate::lib::config::Config;
+use crate::lib::db::Db;
+use crate::lib::export::{DateRange, ExportFormat};
+use crate::lib::import;
+use crate::lib::parse;
+use chrono::{Duration, Local, NaiveDate};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// A fast, lightweight, and intuitive TUI timer application.
+/// Run without a subcommand to open the TUI.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Read the configuration from this file instead of the default location
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Time to track per day (e.g. 8h or 7h30m), shown as progress next to
+    /// each day's total
+    #[arg(long, value_parser = parse::parse_duration)]
+    pub daily_target: Option<Duration>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start a new timer, stopping the one that is currently running unless
+    /// `allow_multiple_running` is set. With --duration, log time that was
+    /// already spent instead.
+    Start {
+        /// Name of the timer
+        name: String,
+        /// Description of the timer
+        #[arg(short, long, default_value = "")]
+        description: String,
+        /// Add a stopped entry of this length (e.g. 2h or 1h30m) without
+        /// touching the running timer
+        #[arg(long)]
+        duration: Option<String>,
+        /// Date of the past entry (today, yesterday, <n>d or YYYY-MM-DD)
+        #[arg(long, requires = "duration")]
+        date: Option<String>,
+        /// Start time of the past entry (HH:MM). Without it the entry ends now.
+        #[arg(long, requires = "duration")]
+        at: Option<String>,
+    },
+    /// Stop the running timer
+    Stop,
+    /// Show the running timer
+    Status,
+    /// List timers grouped by date
+    List {
+        /// Only list timers started on or after this date
+        /// (today, yesterday, <n>d or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Export timers as CSV or JSON
+    Export {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Only export timers started on or after this date
+        #[arg(long)]
+        since: Option<String>,
+        /// Only export timers started on or before this date
+        #[arg(long)]
+        until: Option<String>,
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import timers from a CSV or JSON file in the export format
+    Import {
+        /// File to import
+        file: PathBuf,
+        /// Input format, guessed from the file extension if omitted
+        #[arg(short, long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Validate the file and report what would be imported without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Run a subcommand against the database without starting the TUI
+pub fn run(
+    command: Command,
+    db: &Db,
+    config: &Config,
+    clock: &dyn Clock,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Start {
+            name,
+            description,
+            duration: Some(duration),
+            date,
+            at,
+        } => add_past(db, clock, name, description, &duration, date, at, out),
+        Command::Start {
+            name, description, ..
+        } => start(db, config, clock, name, description, out),
+        Command::Stop => stop(db, clock, out),
+        Command::Status => status(db, clock, out),
+        Command::List { since } => list(db, config, clock, since.as_deref(), out),
+        Command::Export {
+            format,
+            since,
+            until,
+            output,
+        } => {
+            let range = DateRange {
+                since: parse_optional_date(since.as_deref(), clock)?,
+                until: parse_optional_date(until.as_deref(), clock)?,
+            };
+            match output {
+                Some(path) => {
+                    let mut file = BufWriter::new(File::create(&path)?);
+                    let count = db.export_timers(format, &range, clock.now(), &mut file)?;
+                    file.flush()?;
+                    writeln!(out, "Exported {} timers to {}", count, path.display())?;
+                }
+                None => {
+                    db.export_timers(format, &range, clock.now(), out)?;
+                }
+            }
+            Ok(())
+        }
+        Command::Import {
+            file,
+            format,
+            dry_run,
+        } => import(db, &file, format, dry_run, out),
+    }
+}
+
+fn import(
+    db: &Db,
+    file: &Path,
+    format: Option<ExportFormat>,
+    dry_run: bool,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let format = format
+        .or_else(|| import::format_from_path(file))
+        .ok_or("Unable to guess the format from the file name, use --format")?;
+    let input = fs::read_to_string(file)?;
+
+    let report = import::import_timers(db, &input, format, dry_run)?;
+    for error in &report.errors {
+        writeln!(out, "{}", error)?;
+    }
+    writeln!(
+        out,
+        "{} {} timers, skipped {} duplicates and {} invalid records",
+        if dry_run { "Would import" } else { "Imported" },
+        report.imported,
+        report.duplicates,
+        report.errors.len()
+    )?;
+    Ok(())
+}
+
+fn parse_optional_date(date: Option<&str>, clock: &dyn Clock) -> Result<Option<NaiveDate>, String> {
+    date.map(|date| parse::parse_date(date, parse::local_date(clock.now())))
+        .transpose()
+}
+
+fn start(
+    db: &Db,
+    config: &Config,
+    clock: &dyn Clock,
+    name: String,
+    description: String,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    if !config.allow_multiple_running {
+        stop(db, clock, out)?;
+    }
+
+    let mut timer = Timer::new(name, description, clock.now());
+    db.add_timer_to_db(&mut timer)?;
+    writeln!(out, "Started '{}'", timer.name)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_past(
+    db: &Db,
+    clock: &dyn Clock,
+    name: String,
+    description: String,
+    duration: &str,
+    date: Option<String>,
+    at: Option<String>,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let now = clock.now();
+    let (start_time, duration) =
+        parse::parse_past_entry(date.as_deref(), at.as_deref(), duration, now)?;
+
+    let mut timer = Timer::stopped(name, description, start_time, duration);
+    db.add_timer_to_db(&mut timer)?;
+    writeln!(
+        out,
+        "Added '{}' on {} ({})",
+        timer.name,
+        timer
+            .start_time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        timer.formatted_duration(now)
+    )?;
+    Ok(())
+}
+
+fn stop(db: &Db, clock: &dyn Clock, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let now = clock.now();
+    let mut running: Vec<Timer> = db
+        .get_timers_from_db()?
+        .into_iter()
+        .filter(|timer| timer.running)
+        .collect();
+
+    for timer in running.iter_mut() {
+        timer.stop(now);
+        writeln!(
+            out,
+            "Stopped '{}' ({})",
+            timer.name,
+            timer.formatted_duration(now)
+        )?;
+    }
+    db.update_timers_in_db(&mut running)?;
+    Ok(())
+}
+
+fn status(db: &Db, clock: &dyn Clock, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let now = clock.now();
+    let timers = db.get_timers_from_db()?;
+    let mut running = timers.iter().filter(|timer| timer.running).peekable();
+
+    if running.peek().is_none() {
+        writeln!(out, "No timer is running")?;
+    }
+    for timer in running {
+        writeln!(
+            out,
+            "Running '{}' for {}",
+            timer.name,
+            timer.formatted_duration(now)
+        )?;
+    }
+    Ok(())
+}
+
+fn list(
+    db: &Db,
+    config: &Config,
+    clock: &dyn Clock,
+    since: Option<&str>,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let now = clock.now();
+    let range = DateRange {
+        since: parse_optional_date(since, clock)?,
+        until: None,
+    };
+
+    let timers = db.get_timers_from_db()?;
+    let mut current_date = None;
+
+    for timer in timers.iter().filter(|timer| range.contains(timer)) {
+        let date = timer
+            .start_time
+            .with_timezone(&Local)
+            .format(&config.date_format)
+            .to_string();
+        if current_date.as_ref() != Some(&date) {
+            writeln!(out, "{}", date)?;
+            current_date = Some(date);
+        }
+        writeln!(
+            out,
+            "  {:<20} {:<30} {}{}",
+            timer.name,
+            timer.description,
+            timer.formatted_duration(now),
+            if timer.running { " (running)" } else { "" }
+        )?;
+    }
+    Ok(())
+}
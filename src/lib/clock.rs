@@ -0,0 +1,69 @@
+use chrono::{DateTime, Duration, Utc};
+use std::cell::Cell;
+use std::fmt;
+use std::time::Instant;
+
+/// Source of the current time. The app reads the time only through a clock,
+/// so tests can control how time passes.
+pub trait Clock: fmt::Debug {
+    /// The current wall-clock time, used for timestamps and durations
+    fn now(&self) -> DateTime<Utc>;
+
+    /// A monotonic instant, used to time key presses
+    fn instant(&self) -> Instant;
+}
+
+/// The clock of the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to
+#[derive(Debug)]
+pub struct FakeClock {
+    now: Cell<DateTime<Utc>>,
+    start: Instant,
+    elapsed: Cell<std::time::Duration>,
+}
+
+impl FakeClock {
+    pub fn new(now: DateTime<Utc>) -> FakeClock {
+        FakeClock {
+            now: Cell::new(now),
+            start: Instant::now(),
+            elapsed: Cell::new(std::time::Duration::ZERO),
+        }
+    }
+
+    /// Let `duration` pass. Both the wall-clock time and the instant move.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+        let duration = duration.to_std().expect("A fake clock can't go backwards");
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+
+    /// Jump to another wall-clock time, e.g. to pretend the app was started
+    /// on another day. The instant doesn't move.
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now.get()
+    }
+
+    fn instant(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+}
@@ -0,0 +1,193 @@
+use crate::lib::db::Db;
+use crate::lib::error::{Error, Result};
+use crate::lib::keymap::Keymap;
+use crate::lib::parse;
+use chrono::Duration;
+use chrono::format::{Item, StrftimeItems};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings read from `config.toml`. Every setting is optional, missing ones
+/// keep their default.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Database file, `data_dir()/timers/timers.db` if not set
+    pub database_path: Option<PathBuf>,
+    /// strftime format of the date rows
+    #[serde(deserialize_with = "deserialize_date_format")]
+    pub date_format: String,
+    /// Keep other timers running when one is started
+    pub allow_multiple_running: bool,
+    /// Time to track per day, shown as progress next to each day's total
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub daily_target: Option<Duration>,
+    /// How often the screen is redrawn
+    #[serde(deserialize_with = "deserialize_tick_rate")]
+    pub tick_rate_ms: u64,
+    pub delete_confirmation: DeleteConfirmation,
+    /// Time allowed between the two presses of `dd`
+    pub delete_window_ms: u64,
+    /// Days deleted timers stay in the trash, 0 keeps them until purged by hand
+    pub trash_retention_days: u32,
+    pub theme: Theme,
+    /// Key bindings, only the rebound actions have to be listed
+    pub keys: Keymap,
+}
+
+/// How deleting a timer with `d` is confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeleteConfirmation {
+    /// Press `d` twice within `delete_window_ms`
+    DoublePress,
+    /// Confirm in a dialog
+    Dialog,
+    /// Delete on the first press
+    None,
+}
+
+/// Colors of the TUI. Accepts names like `gray` and hex values like `#ff8800`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub date_row_bg: Color,
+    pub date_row_fg: Color,
+    pub error: Color,
+    pub target_reached: Color,
+    pub daily_chart: Color,
+    pub name_chart: Color,
+    pub tag_colors: Vec<Color>,
+    pub search_match: Color,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            database_path: None,
+            date_format: "%d-%m-%Y".to_string(),
+            allow_multiple_running: false,
+            daily_target: None,
+            tick_rate_ms: 16,
+            delete_confirmation: DeleteConfirmation::DoublePress,
+            delete_window_ms: 500,
+            trash_retention_days: 30,
+            theme: Theme::default(),
+            keys: Keymap::default(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            date_row_bg: Color::Gray,
+            date_row_fg: Color::White,
+            error: Color::Red,
+            target_reached: Color::Green,
+            daily_chart: Color::Cyan,
+            name_chart: Color::Green,
+            tag_colors: vec![
+                Color::Blue,
+                Color::Green,
+                Color::Magenta,
+                Color::Cyan,
+                Color::Yellow,
+                Color::Red,
+            ],
+            search_match: Color::Yellow,
+        }
+    }
+}
+
+impl Config {
+    /// The platform-appropriate config file, e.g. `~/.config/timers/config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("timers").join("config.toml"))
+    }
+
+    /// Load the config file at `path`, or the default config file if `path` is
+    /// `None`. Only the default file may be missing.
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Config::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Config::from_toml(&contents).map_err(|e| {
+                Error::Config(format!("Invalid config file {}: {}", path.display(), e))
+            }),
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Config::default())
+            }
+            Err(e) => Err(Error::Config(format!(
+                "Unable to read config file {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Open the configured database, creating its directory if needed
+    pub fn open_db(&self) -> Result<Db> {
+        let Some(path) = &self.database_path else {
+            return Db::new_with_default_path();
+        };
+        let path = expand_home(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| Error::Config("Database path contains invalid UTF-8".to_string()))?;
+        Db::open(path_str)
+    }
+}
+
+/// Resolve a leading `~` to the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|input| parse::parse_duration(&input).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Reject a tick rate of 0, which would make the event loop spin without
+/// waiting for input
+fn deserialize_tick_rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let tick_rate = u64::deserialize(deserializer)?;
+    if tick_rate == 0 {
+        return Err(serde::de::Error::custom("tick_rate_ms must be at least 1"));
+    }
+    Ok(tick_rate)
+}
+
+/// Reject formats chrono can't render, as rendering them would panic
+fn deserialize_date_format<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let format = String::deserialize(deserializer)?;
+    if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+        return Err(serde::de::Error::custom(format!(
+            "invalid date format '{}'",
+            format
+        )));
+    }
+    Ok(format)
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the panic hook writes its report, next to the default database
+pub fn report_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("timers").join("crash.log"))
+}
+
+/// The text of a crash report
+pub fn format_report(
+    message: &str,
+    location: Option<&str>,
+    backtrace: &str,
+    app_state: &str,
+    time: DateTime<Utc>,
+) -> String {
+    format!(
+        "time-rs {} crashed at {}\n\n\
+        Panic: {}\n\
+        Location: {}\n\n\
+        App state:\n{}\n\n\
+        Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        time.to_rfc3339(),
+        message,
+        location.unwrap_or("unknown"),
+        app_state,
+        backtrace
+    )
+}
+
+/// Write a crash report to `path`, creating its directory if needed
+pub fn write_report(path: &Path, report: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, report)
+}
+
+/// Tell the user about the report of the last crash and print it if they
+/// answer yes. The report is then moved aside so it is only offered once.
+pub fn offer_report(path: &Path, input: &mut impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    let report = fs::read_to_string(path)?;
+    write!(
+        out,
+        "time-rs crashed last time, a report was written to {}.\nShow it now? [y/N] ",
+        path.display()
+    )?;
+    out.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        writeln!(out, "\n{}", report)?;
+    }
+
+    let old_path = path.with_extension("log.old");
+    fs::rename(path, &old_path)?;
+    writeln!(out, "The report was moved to {}", old_path.display())?;
+    Ok(())
+}
@@ -1,10 +1,15 @@
-use crate::lib::app::Timer;
-use chrono::{DateTime, Duration};
-use rusqlite::{Connection, params};
-use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
-use std::fs;
+use crate::lib::app::{Project, Session, Timer};
+use crate::lib::error::{Error, Result};
+use crate::lib::export::{self, DateRange, ExportFormat};
+use crate::lib::migrations;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use dirs;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 #[derive(Debug)]
 pub struct Db {
@@ -12,16 +17,17 @@ pub struct Db {
 }
 
 impl Db {
-    pub fn new(path: &str) -> Self {
-        Db {
-            conn: Db::init_db(path).expect("Unable to init db"),
-        }
+    /// Open the database at `path`, migrating its schema to the latest version
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Db {
+            conn: Db::init_db(path)?,
+        })
     }
 
     /// Get the platform-appropriate database path
-    pub fn get_database_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    pub fn get_database_path() -> Result<PathBuf> {
         let data_dir = dirs::data_dir()
-            .ok_or("Unable to determine data directory")?;
+            .ok_or_else(|| Error::Config("Unable to determine data directory".to_string()))?;
 
         let app_dir = data_dir.join("timers");
 
@@ -32,101 +38,496 @@ impl Db {
     }
 
     /// Create a new Db instance using the platform-appropriate path
-    pub fn new_with_default_path() -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new_with_default_path() -> Result<Self> {
         let db_path = Self::get_database_path()?;
-        let path_str = db_path.to_str()
-            .ok_or("Database path contains invalid UTF-8")?;
+        let path_str = db_path
+            .to_str()
+            .ok_or_else(|| Error::Config("Database path contains invalid UTF-8".to_string()))?;
 
-        Ok(Db {
-            conn: Db::init_db(path_str).map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?,
-        })
+        Db::open(path_str)
     }
 
-    fn init_db(path: &str) -> Result<Arc<Mutex<Connection>>, rusqlite::Error> {
-        let conn = Connection::open(path)?;
+    fn init_db(path: &str) -> Result<Arc<Mutex<Connection>>> {
+        let mut conn = Connection::open(path)?;
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS timers (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            description TEXT NOT NULL,
-            start_time DATETIME NOT NULL,
-            duration INTEGER NOT NULL,
-            running BOOLEAN NOT NULL
-        )",
-            [],
-        )?;
+        migrations::run(&mut conn)?;
 
         Ok(Arc::new(Mutex::new(conn))) // Wrap the connection in an Arc<Mutex<Connection>>conn)
     }
 
-    pub fn add_timer_to_db(&self, timer: &mut Timer) -> Result<(), rusqlite::Error> {
-        let conn = self.conn.lock().expect("Unable to lock connection");
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| Error::ConnectionPoisoned)
+    }
+
+    pub fn add_timer_to_db(&self, timer: &mut Timer) -> Result<()> {
+        let conn = self.conn()?;
+        Db::insert_timer(&conn, timer)?;
+        Ok(())
+    }
+
+    /// Add several timers in one transaction, so either all or none of them
+    /// are stored
+    pub fn add_timers_to_db(&self, timers: &mut [Timer]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        for timer in timers {
+            Db::insert_timer(&tx, timer)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn insert_timer(conn: &Connection, timer: &mut Timer) -> Result<(), rusqlite::Error> {
         conn.execute(
-            "INSERT INTO timers (name, description, start_time, duration, running) VALUES (?, ?, ?, ?, ?)",
+            "INSERT INTO timers (name, description, start_time, running, project_id) VALUES (?, ?, ?, ?, ?)",
             params![
-            timer.name,
-            timer.description,
-            timer.start_time.to_rfc3339(),
-            timer.duration.num_seconds(),
-            timer.running
-        ],
+                timer.name,
+                timer.description,
+                timer.start_time.to_rfc3339(),
+                timer.running,
+                timer.project_id
+            ],
         )?;
 
         let id = conn.last_insert_rowid();
         timer.id = id as usize;
+        Db::save_sessions(conn, timer)?;
+        Db::save_tags(conn, timer)?;
+        Ok(())
+    }
+
+    /// Replace the tags of a timer, creating tags that don't exist yet
+    fn save_tags(conn: &Connection, timer: &Timer) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM timer_tags WHERE timer_id = ?",
+            params![timer.id],
+        )?;
+        for tag in &timer.tags {
+            conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?)", params![tag])?;
+            conn.execute(
+                "INSERT OR IGNORE INTO timer_tags (timer_id, tag_id)
+                SELECT ?, id FROM tags WHERE name = ?",
+                params![timer.id, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Insert new sessions of a timer and update the end of existing ones.
+    /// Running sessions are stored without an end, so their elapsed time keeps
+    /// counting from the persisted start while the app is closed.
+    fn save_sessions(conn: &Connection, timer: &mut Timer) -> Result<(), rusqlite::Error> {
+        for session in timer.sessions.iter_mut() {
+            if session.id == 0 {
+                conn.execute(
+                    "INSERT INTO timer_sessions (timer_id, started_at, ended_at) VALUES (?, ?, ?)",
+                    params![
+                        timer.id,
+                        session.started_at.to_rfc3339(),
+                        session.ended_at.map(|t| t.to_rfc3339())
+                    ],
+                )?;
+                session.id = conn.last_insert_rowid() as usize;
+            } else {
+                Db::update_session(conn, session)?;
+            }
+        }
         Ok(())
     }
 
-    pub fn get_timers_from_db(&self) -> Result<Vec<Timer>, rusqlite::Error> {
-        let conn = self.conn.lock().expect("Unable to lock connection");
-        let mut stmt = conn
-            .prepare("SELECT id, name, description, start_time, duration, running FROM timers")?;
-        let timers = stmt
+    fn update_session(conn: &Connection, session: &Session) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE timer_sessions SET started_at = ?, ended_at = ? WHERE id = ?",
+            params![
+                session.started_at.to_rfc3339(),
+                session.ended_at.map(|t| t.to_rfc3339()),
+                session.id
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// All timers that aren't in the trash, oldest first
+    pub fn get_timers_from_db(&self) -> Result<Vec<Timer>> {
+        self.load_timers("deleted_at IS NULL ORDER BY start_time, id")
+    }
+
+    /// The timers in the trash, most recently deleted first
+    pub fn get_deleted_timers(&self) -> Result<Vec<Timer>> {
+        self.load_timers("deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC")
+    }
+
+    /// A single timer, whether it is in the trash or not
+    pub fn get_timer(&self, id: usize) -> Result<Option<Timer>> {
+        Ok(self.load_timers(&format!("id = {id}"))?.pop())
+    }
+
+    /// Load the timers matching the SQL `filter`, with their sessions and tags.
+    /// Only the sessions and tags of those timers are read.
+    fn load_timers(&self, filter: &str) -> Result<Vec<Timer>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, description, start_time, running, project_id, deleted_at FROM timers WHERE {filter}"
+        ))?;
+        let mut timers = stmt
             .query_map(params![], |row| {
                 let timestamp: String = row.get(3)?;
-                let date = DateTime::parse_from_rfc3339(&timestamp).unwrap().to_utc();
+                let deleted_at: Option<String> = row.get(6)?;
                 Ok(Timer {
                     id: row.get(0)?,
                     name: row.get(1)?,
                     description: row.get(2)?,
-                    start_time: date,
-                    duration: Duration::seconds(row.get(4)?),
-                    running: row.get(5)?,
+                    start_time: parse_timestamp(&timestamp)?,
+                    sessions: Vec::new(),
+                    running: row.get(4)?,
+                    project_id: row.get(5)?,
+                    tags: Vec::new(),
+                    deleted_at: deleted_at.as_deref().map(parse_timestamp).transpose()?,
                 })
             })?
             .collect::<Result<Vec<Timer>, rusqlite::Error>>()?;
+
+        let index_by_id: HashMap<usize, usize> = timers
+            .iter()
+            .enumerate()
+            .map(|(index, timer)| (timer.id, index))
+            .collect();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, timer_id, started_at, ended_at FROM timer_sessions
+            WHERE timer_id IN (SELECT id FROM timers WHERE {filter})
+            ORDER BY started_at, id"
+        ))?;
+        let sessions = stmt
+            .query_map(params![], |row| {
+                let started_at: String = row.get(2)?;
+                let ended_at: Option<String> = row.get(3)?;
+                Ok((
+                    row.get::<_, usize>(1)?,
+                    Session {
+                        id: row.get(0)?,
+                        started_at: parse_timestamp(&started_at)?,
+                        ended_at: ended_at.as_deref().map(parse_timestamp).transpose()?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<(usize, Session)>, rusqlite::Error>>()?;
+
+        for (timer_id, session) in sessions {
+            if let Some(&index) = index_by_id.get(&timer_id) {
+                timers[index].sessions.push(session);
+            }
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT timer_tags.timer_id, tags.name FROM timer_tags
+            JOIN tags ON tags.id = timer_tags.tag_id
+            WHERE timer_tags.timer_id IN (SELECT id FROM timers WHERE {filter})
+            ORDER BY tags.name"
+        ))?;
+        let tags = stmt
+            .query_map(params![], |row| {
+                Ok((row.get::<_, usize>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<(usize, String)>, rusqlite::Error>>()?;
+
+        for (timer_id, tag) in tags {
+            if let Some(&index) = index_by_id.get(&timer_id) {
+                timers[index].tags.push(tag);
+            }
+        }
         Ok(timers)
     }
 
-    pub fn update_timers_in_db(&self, timers: &Vec<Timer>) -> Result<(), rusqlite::Error> {
-        let conn = self.conn.lock().expect("Unable to lock connection");
+    /// Write all timers started within `range` to `writer`, returning how many
+    /// were exported. Running timers are exported with their duration at `now`.
+    pub fn export_timers(
+        &self,
+        format: ExportFormat,
+        range: &DateRange,
+        now: DateTime<Utc>,
+        writer: &mut impl Write,
+    ) -> Result<usize> {
+        let timers: Vec<Timer> = self
+            .get_timers_from_db()?
+            .into_iter()
+            .filter(|timer| range.contains(timer))
+            .collect();
+        export::write_timers(&timers, format, now, writer)?;
+        Ok(timers.len())
+    }
+
+    pub fn update_timers_in_db(&self, timers: &mut [Timer]) -> Result<()> {
+        let conn = self.conn()?;
         for timer in timers {
             conn.execute(
-                "UPDATE timers SET duration = ?, running = ? WHERE id = ?",
-                params![timer.duration.num_seconds(), timer.running, timer.id],
+                "UPDATE timers SET running = ? WHERE id = ?",
+                params![timer.running, timer.id],
             )?;
+            Db::save_sessions(&conn, timer)?;
         }
         Ok(())
     }
 
-    pub fn delete_timer(&self, id: usize) -> Result<(), rusqlite::Error> {
-        let conn = self.conn.lock().expect("Unable to lock connection");
+    /// Time tracked per day from `since` on, for days with any time tracked.
+    /// Sessions count towards the day they started on, and running sessions
+    /// count until `now`.
+    pub fn daily_totals(
+        &self,
+        since: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(NaiveDate, Duration)>> {
+        self.session_totals("date(started_at, 'localtime')", since, now)?
+            .into_iter()
+            .map(|(day, total)| Ok((parse_date(&day)?, total)))
+            .collect()
+    }
+
+    /// Time tracked per week (starting on Monday) from `since` on, keyed by the
+    /// first day of the week
+    pub fn weekly_totals(
+        &self,
+        since: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(NaiveDate, Duration)>> {
+        self.session_totals(
+            "date(started_at, 'localtime', 'weekday 0', '-6 days')",
+            since,
+            now,
+        )?
+        .into_iter()
+        .map(|(week, total)| Ok((parse_date(&week)?, total)))
+        .collect()
+    }
+
+    /// Time tracked per timer name from `since` on, longest first
+    pub fn totals_by_name(
+        &self,
+        since: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(String, Duration)>> {
+        let mut totals = self.session_totals("timers.name", since, now)?;
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(totals)
+    }
+
+    /// Sum the length of all sessions started on or after the local date
+    /// `since`, grouped by the SQL expression `group_by`. Days are local, like
+    /// the date rows of the table.
+    fn session_totals(
+        &self,
+        group_by: &str,
+        since: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(String, Duration)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {group_by} AS key,
+                CAST(ROUND(SUM(julianday(COALESCE(ended_at, ?1)) - julianday(started_at)) * 86400) AS INTEGER)
+            FROM timer_sessions
+            JOIN timers ON timers.id = timer_sessions.timer_id
+            WHERE date(started_at, 'localtime') >= ?2 AND timers.deleted_at IS NULL
+            GROUP BY key
+            ORDER BY key"
+        ))?;
+        let totals = stmt
+            .query_map(params![now.to_rfc3339(), since.to_string()], |row| {
+                Ok((row.get(0)?, Duration::seconds(row.get(1)?)))
+            })?
+            .collect::<Result<Vec<(String, Duration)>, rusqlite::Error>>()?;
+        Ok(totals)
+    }
+
+    /// All projects, ordered by client and project name
+    pub fn get_projects(&self) -> Result<Vec<Project>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT projects.id, projects.name, clients.name FROM projects
+            LEFT JOIN clients ON clients.id = projects.client_id
+            ORDER BY clients.name IS NOT NULL, clients.name, projects.name",
+        )?;
+        let projects = stmt
+            .query_map(params![], |row| {
+                Ok(Project {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    client: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<Project>, rusqlite::Error>>()?;
+        Ok(projects)
+    }
+
+    /// Look up the project for input of the form `Client / Project` or
+    /// `Project`, creating the client and project if they don't exist yet.
+    /// Empty input means no project.
+    pub fn find_or_create_project(&self, input: &str) -> Result<Option<usize>> {
+        let Some((client, name)) = Project::parse_display_name(input) else {
+            return Ok(None);
+        };
+        let conn = self.conn()?;
+
+        let client_id: Option<i64> = match client {
+            Some(client) => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO clients (name) VALUES (?)",
+                    params![client],
+                )?;
+                Some(conn.query_row(
+                    "SELECT id FROM clients WHERE name = ?",
+                    params![client],
+                    |row| row.get(0),
+                )?)
+            }
+            None => None,
+        };
+
+        let existing = conn
+            .query_row(
+                "SELECT id FROM projects WHERE name = ? AND client_id IS ?",
+                params![name, client_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            return Ok(Some(id));
+        }
+
+        conn.execute(
+            "INSERT INTO projects (client_id, name) VALUES (?, ?)",
+            params![client_id, name],
+        )?;
+        Ok(Some(conn.last_insert_rowid() as usize))
+    }
+
+    /// Move a timer to the trash. A running timer is stopped at `now`.
+    pub fn delete_timer(&self, id: usize, now: DateTime<Utc>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE timer_sessions SET ended_at = ?1 WHERE timer_id = ?2 AND ended_at IS NULL",
+            params![now.to_rfc3339(), id],
+        )?;
+        conn.execute(
+            "UPDATE timers SET running = 0, deleted_at = ?1 WHERE id = ?2",
+            params![now.to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Take a timer out of the trash
+    pub fn restore_timer(&self, id: usize) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE timers SET deleted_at = NULL WHERE id = ?",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete every timer that was moved to the trash before `cutoff` for
+    /// good, returning the ids of the purged timers
+    pub fn purge_deleted_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<usize>> {
+        let ids = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(
+                "SELECT id FROM timers WHERE deleted_at IS NOT NULL AND julianday(deleted_at) < julianday(?)",
+            )?;
+            stmt.query_map(params![cutoff.to_rfc3339()], |row| row.get(0))?
+                .collect::<Result<Vec<usize>, rusqlite::Error>>()?
+        };
+        for id in &ids {
+            self.purge_timer(*id)?;
+        }
+        Ok(ids)
+    }
+
+    /// Delete a timer with its sessions and tags for good
+    pub fn purge_timer(&self, id: usize) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM timer_sessions WHERE timer_id = ?", params![id])?;
+        conn.execute("DELETE FROM timer_tags WHERE timer_id = ?", params![id])?;
         conn.execute("DELETE FROM timers WHERE id = ?", params![id])?;
         Ok(())
     }
 
-    pub fn edit_timer(
-        &self,
-        timer: &Timer,
-        name: &str,
-        description: &str,
-    ) -> Result<(), rusqlite::Error> {
-        let conn = self.conn.lock().expect("Unable to lock connection");
+    /// Store `timer` exactly as given, keeping its id and the ids of its
+    /// sessions. Used to bring back an earlier state of a timer, which may
+    /// have been purged since.
+    pub fn save_timer(&self, timer: &Timer) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO timers (id, name, description, start_time, running, project_id, deleted_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(id) DO UPDATE SET name = ?2, description = ?3, start_time = ?4,
+                running = ?5, project_id = ?6, deleted_at = ?7",
+            params![
+                timer.id,
+                timer.name,
+                timer.description,
+                timer.start_time.to_rfc3339(),
+                timer.running,
+                timer.project_id,
+                timer.deleted_at.map(|t| t.to_rfc3339())
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM timer_sessions WHERE timer_id = ?",
+            params![timer.id],
+        )?;
+        for session in &timer.sessions {
+            tx.execute(
+                "INSERT INTO timer_sessions (id, timer_id, started_at, ended_at) VALUES (?, ?, ?, ?)",
+                params![
+                    session.id,
+                    timer.id,
+                    session.started_at.to_rfc3339(),
+                    session.ended_at.map(|t| t.to_rfc3339())
+                ],
+            )?;
+        }
+        Db::save_tags(&tx, timer)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Save a new name and description, together with the start time, project,
+    /// tags and sessions of `timer`, which may have been adjusted. Sessions
+    /// added by the adjustment are inserted and get their ids.
+    pub fn edit_timer(&self, timer: &mut Timer, name: &str, description: &str) -> Result<()> {
+        let conn = self.conn()?;
         conn.execute(
-            "UPDATE timers SET name = ?, description = ? WHERE id = ?",
-            params![name, description, timer.id],
+            "UPDATE timers SET name = ?, description = ?, start_time = ?, project_id = ? WHERE id = ?",
+            params![
+                name,
+                description,
+                timer.start_time.to_rfc3339(),
+                timer.project_id,
+                timer.id
+            ],
         )?;
+        Db::save_sessions(&conn, timer)?;
+        Db::save_tags(&conn, timer)?;
         Ok(())
     }
 }
+
+fn parse_date(date: &str) -> Result<NaiveDate, rusqlite::Error> {
+    date.parse().map_err(|e: chrono::ParseError| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+    })
+}
+
+/// Parse a timestamp column of the row being read. A malformed timestamp
+/// fails the query with [`Error::InvalidTimestamp`] instead of panicking.
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>, rusqlite::Error> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|timestamp| timestamp.to_utc())
+        .map_err(|_| {
+            rusqlite::Error::FromSqlConversionFailure(
+                0,
+                rusqlite::types::Type::Text,
+                Box::new(Error::InvalidTimestamp(timestamp.to_string())),
+            )
+        })
+}
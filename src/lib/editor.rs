@@ -0,0 +1,54 @@
+use crate::lib::error::{Error, Result};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+
+/// The editor descriptions are opened in: `$VISUAL`, then `$EDITOR`, then
+/// the platform's default
+pub fn command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        })
+}
+
+/// Open `text` in an editor and return what was saved. `command` may contain
+/// arguments, e.g. `code --wait`; the path of a temporary file is appended.
+/// The file gets a random name and is only readable by the user, as the
+/// description may be private. Trailing line breaks added by the editor are
+/// removed.
+pub fn edit(command: &str, text: &str) -> Result<String> {
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| Error::Editor("No editor set, set $EDITOR".to_string()))?;
+
+    let mut file = tempfile::Builder::new()
+        .prefix("time-rs-")
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+    let status = Command::new(program).args(words).arg(file.path()).status();
+    // Editors may replace the file instead of writing to it, so read it by path
+    let edited = fs::read_to_string(file.path());
+    drop(file);
+
+    let status =
+        status.map_err(|e| Error::Editor(format!("Could not run '{}': {}", command, e)))?;
+    if !status.success() {
+        return Err(Error::Editor(format!(
+            "'{}' exited with {}, the description was not changed",
+            command, status
+        )));
+    }
+    Ok(edited?.trim_end_matches(['\n', '\r']).to_string())
+}
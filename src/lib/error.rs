@@ -0,0 +1,93 @@
+use std::fmt;
+
+/// Everything that can go wrong outside of validating user input
+#[derive(Debug)]
+pub enum Error {
+    Db(rusqlite::Error),
+    Io(std::io::Error),
+    /// Writing an export as CSV failed
+    Csv(csv::Error),
+    /// Writing an export as JSON failed
+    Json(serde_json::Error),
+    /// The database was written by a newer version of the application
+    SchemaTooNew {
+        version: i64,
+        latest: i64,
+    },
+    /// A timestamp in the database that isn't RFC 3339
+    InvalidTimestamp(String),
+    /// Another thread panicked while holding the database connection
+    ConnectionPoisoned,
+    Config(String),
+    /// Input that was rejected, shown next to the dialog it was typed into
+    InvalidInput(String),
+    /// The external editor could not be run or failed
+    Editor(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Db(e) => write!(f, "Database error: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Csv(e) => write!(f, "CSV error: {}", e),
+            Error::Json(e) => write!(f, "JSON error: {}", e),
+            Error::SchemaTooNew { version, latest } => write!(
+                f,
+                "Database schema version {} is newer than the supported version {}, please update time-rs",
+                version, latest
+            ),
+            Error::InvalidTimestamp(timestamp) => {
+                write!(f, "Invalid timestamp '{}' in the database", timestamp)
+            }
+            Error::ConnectionPoisoned => write!(f, "The database connection is unusable"),
+            Error::Config(message) | Error::InvalidInput(message) | Error::Editor(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Db(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Csv(e) => Some(e),
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        // Bad rows surface from inside rusqlite's row mapping
+        if let rusqlite::Error::FromSqlConversionFailure(_, _, source) = &e
+            && let Some(Error::InvalidTimestamp(timestamp)) = source.downcast_ref::<Error>()
+        {
+            return Error::InvalidTimestamp(timestamp.clone());
+        }
+        Error::Db(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
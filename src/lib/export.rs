@@ -0,0 +1,95 @@
+use crate::lib::app::Timer;
+use crate::lib::error::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Inclusive range of dates a timer's start time has to fall into.
+/// An open end matches everything on that side.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DateRange {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn contains(&self, timer: &Timer) -> bool {
+        let date = timer.local_date();
+        self.since.is_none_or(|since| date >= since) && self.until.is_none_or(|until| date <= until)
+    }
+}
+
+/// A timer as it is written to CSV and JSON exports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimerRecord {
+    pub id: usize,
+    pub name: String,
+    pub description: String,
+    pub start_time: DateTime<Utc>,
+    pub duration_seconds: i64,
+    pub running: bool,
+}
+
+impl TimerRecord {
+    pub const FIELDS: [&'static str; 6] = [
+        "id",
+        "name",
+        "description",
+        "start_time",
+        "duration_seconds",
+        "running",
+    ];
+}
+
+impl TimerRecord {
+    /// The record of a timer, with a running timer counted up to `now`
+    pub fn new(timer: &Timer, now: DateTime<Utc>) -> Self {
+        TimerRecord {
+            id: timer.id,
+            name: timer.name.clone(),
+            description: timer.description.clone(),
+            start_time: timer.start_time,
+            duration_seconds: timer.duration(now).num_seconds(),
+            running: timer.running,
+        }
+    }
+}
+
+/// Write `timers` to `writer` in the given format
+pub fn write_timers(
+    timers: &[Timer],
+    format: ExportFormat,
+    now: DateTime<Utc>,
+    writer: &mut impl Write,
+) -> Result<()> {
+    let records: Vec<TimerRecord> = timers
+        .iter()
+        .map(|timer| TimerRecord::new(timer, now))
+        .collect();
+
+    match format {
+        ExportFormat::Csv => {
+            // Headers are written by hand so that an empty export still has them
+            let mut csv_writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(writer);
+            csv_writer.write_record(TimerRecord::FIELDS)?;
+            for record in &records {
+                csv_writer.serialize(record)?;
+            }
+            csv_writer.flush()?;
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &records)?;
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::lib::app::Timer;
+use crate::lib::db::Db;
+use crate::lib::error::Result;
+
+/// How many steps can be undone
+const LIMIT: usize = 100;
+
+/// The state of one timer before and after a change. `None` means the timer
+/// didn't exist, a timer in the trash has `deleted_at` set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub id: usize,
+    pub before: Option<Timer>,
+    pub after: Option<Timer>,
+}
+
+/// The changes made by a single action, e.g. starting a timer also stops the
+/// one that was running
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub label: &'static str,
+    pub changes: Vec<Change>,
+}
+
+/// Undo and redo stacks of the changes made to the timers
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Remember a step so it can be undone. Making a new change discards the
+    /// steps that could be redone.
+    pub fn record(&mut self, step: Step) {
+        if step.changes.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(step);
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Drop every step that changed one of the timers `ids`, which were
+    /// deleted for good. Undoing or redoing them would bring the timers back.
+    pub fn forget(&mut self, ids: &[usize]) {
+        let keep = |step: &Step| !step.changes.iter().any(|change| ids.contains(&change.id));
+        self.undo.retain(keep);
+        self.redo.retain(keep);
+    }
+
+    /// Write the state before the last step back to the database and return
+    /// that step, or `None` if there is nothing to undo
+    pub fn undo(&mut self, db: &Db) -> Result<Option<&Step>> {
+        let Some(step) = self.undo.pop() else {
+            return Ok(None);
+        };
+        let result = step
+            .changes
+            .iter()
+            .rev()
+            .try_for_each(|change| restore(db, change.id, change.before.as_ref()));
+        if let Err(e) = result {
+            self.undo.push(step);
+            return Err(e);
+        }
+        self.redo.push(step);
+        Ok(self.redo.last())
+    }
+
+    /// Write the state after the last undone step back to the database and
+    /// return that step, or `None` if there is nothing to redo
+    pub fn redo(&mut self, db: &Db) -> Result<Option<&Step>> {
+        let Some(step) = self.redo.pop() else {
+            return Ok(None);
+        };
+        let result = step
+            .changes
+            .iter()
+            .try_for_each(|change| restore(db, change.id, change.after.as_ref()));
+        if let Err(e) = result {
+            self.redo.push(step);
+            return Err(e);
+        }
+        self.undo.push(step);
+        Ok(self.undo.last())
+    }
+}
+
+/// The timers that differ between `before` and `after`, both lists of the
+/// timers shown on the main screen. The new state is read from `db`, so
+/// timers that were moved to the trash are recorded with their `deleted_at`.
+pub fn diff(before: &[Timer], after: &[Timer], db: &Db) -> Result<Vec<Change>> {
+    let find = |timers: &[Timer], id: usize| timers.iter().find(|timer| timer.id == id).cloned();
+
+    let mut ids: Vec<usize> = before.iter().chain(after).map(|timer| timer.id).collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut changes = Vec::new();
+    for id in ids {
+        let old = find(before, id);
+        if old.is_some() && old == find(after, id) {
+            continue;
+        }
+        changes.push(Change {
+            id,
+            before: old,
+            after: db.get_timer(id)?,
+        });
+    }
+    Ok(changes)
+}
+
+fn restore(db: &Db, id: usize, state: Option<&Timer>) -> Result<()> {
+    match state {
+        Some(timer) => db.save_timer(timer),
+        None => db.purge_timer(id),
+    }
+}
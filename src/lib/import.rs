@@ -0,0 +1,206 @@
+use crate::lib::app::Timer;
+use crate::lib::db::Db;
+use crate::lib::error::Result;
+use crate::lib::export::{ExportFormat, TimerRecord};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::value::RawValue;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// A record that could not be imported, with the line it starts on
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub errors: Vec<ImportError>,
+}
+
+/// Import timers in the shape written by an export. Malformed records are
+/// reported and skipped, as are records with the same name and start time as
+/// an existing timer. With `dry_run` nothing is written to the database.
+///
+/// Imported timers are always stopped, with a single session covering their
+/// duration.
+pub fn import_timers(
+    db: &Db,
+    input: &str,
+    format: ExportFormat,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let (records, errors) = match format {
+        ExportFormat::Csv => read_csv(input),
+        ExportFormat::Json => read_json(input),
+    };
+    let mut report = ImportReport {
+        errors,
+        ..ImportReport::default()
+    };
+
+    let mut seen: HashSet<(String, DateTime<Utc>)> = db
+        .get_timers_from_db()?
+        .into_iter()
+        .map(|timer| (timer.name, timer.start_time))
+        .collect();
+
+    let mut timers = Vec::new();
+    for (line, record) in records {
+        let duration = match validate_duration(&record) {
+            Ok(duration) => duration,
+            Err(message) => {
+                report.errors.push(ImportError { line, message });
+                continue;
+            }
+        };
+        if !seen.insert((record.name.clone(), record.start_time)) {
+            report.duplicates += 1;
+            continue;
+        }
+
+        timers.push(Timer::stopped(
+            record.name,
+            record.description,
+            record.start_time,
+            duration,
+        ));
+    }
+
+    // A dry run checks exactly what a real import would store
+    if !dry_run {
+        db.add_timers_to_db(&mut timers)?;
+    }
+    report.imported = timers.len();
+    report.errors.sort_by_key(|error| error.line);
+    Ok(report)
+}
+
+/// The duration of a record, which must not be negative and must end at a
+/// representable time
+fn validate_duration(record: &TimerRecord) -> Result<Duration, String> {
+    if record.duration_seconds < 0 {
+        return Err(format!("negative duration {}", record.duration_seconds));
+    }
+    Duration::try_seconds(record.duration_seconds)
+        .filter(|&duration| record.start_time.checked_add_signed(duration).is_some())
+        .ok_or_else(|| format!("duration {} is too long", record.duration_seconds))
+}
+
+/// Guess the format of a file from its extension
+pub fn format_from_path(path: &Path) -> Option<ExportFormat> {
+    match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "csv" => Some(ExportFormat::Csv),
+        "json" => Some(ExportFormat::Json),
+        _ => None,
+    }
+}
+
+fn read_csv(input: &str) -> (Vec<(usize, TimerRecord)>, Vec<ImportError>) {
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    let mut reader = csv::Reader::from_reader(input.as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            errors.push(ImportError {
+                line: 1,
+                message: e.to_string(),
+            });
+            return (records, errors);
+        }
+    };
+
+    for result in reader.records() {
+        let (line, record) = match result {
+            Ok(row) => {
+                let line = row.position().map_or(0, |p| p.line() as usize);
+                (line, row.deserialize::<TimerRecord>(Some(&headers)))
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line() as usize);
+                (line, Err(e))
+            }
+        };
+        match record {
+            Ok(record) => records.push((line, record)),
+            Err(e) => errors.push(ImportError {
+                line,
+                message: csv_error_message(&e, &headers),
+            }),
+        }
+    }
+
+    (records, errors)
+}
+
+/// The csv crate prefixes its messages with the position, which is reported
+/// separately
+fn csv_error_message(error: &csv::Error, headers: &csv::StringRecord) -> String {
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+            Some(field) => format!(
+                "field '{}': {}",
+                headers.get(field as usize).unwrap_or("?"),
+                err.kind()
+            ),
+            None => format!("invalid record: {}", err.kind()),
+        },
+        csv::ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!("expected {} fields, found {}", expected_len, len),
+        _ => error.to_string(),
+    }
+}
+
+fn read_json(input: &str) -> (Vec<(usize, TimerRecord)>, Vec<ImportError>) {
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+
+    // Parse the array lazily so every element keeps its position in the input
+    let elements: Vec<&RawValue> = match serde_json::from_str(input) {
+        Ok(elements) => elements,
+        Err(e) => {
+            errors.push(ImportError {
+                line: e.line(),
+                message: json_error_message(&e),
+            });
+            return (records, errors);
+        }
+    };
+
+    for element in elements {
+        let offset = element.get().as_ptr() as usize - input.as_ptr() as usize;
+        let line = input[..offset].matches('\n').count() + 1;
+        match serde_json::from_str::<TimerRecord>(element.get()) {
+            Ok(record) => records.push((line, record)),
+            Err(e) => errors.push(ImportError {
+                line,
+                message: json_error_message(&e),
+            }),
+        }
+    }
+
+    (records, errors)
+}
+
+/// serde_json appends the position to its messages, which is reported
+/// separately
+fn json_error_message(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    }
+}
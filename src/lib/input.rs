@@ -0,0 +1,191 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Position, Rect};
+use ratatui::style::Style;
+use ratatui::widgets::StatefulWidget;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A single line of editable text with a cursor. The cursor always sits on a
+/// grapheme boundary, so characters made of several code points (emoji,
+/// accented letters) are moved over and deleted as one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextInput {
+    value: String,
+    cursor: usize, // byte offset into `value`
+    scroll: usize, // byte offset of the first visible grapheme
+}
+
+impl TextInput {
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The byte offset of the cursor
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the text and put the cursor at its end
+    pub fn set(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+        self.scroll = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.value.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert pasted text at the cursor. Line breaks and tabs become spaces,
+    /// other control characters are dropped.
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text
+            .replace("\r\n", " ")
+            .chars()
+            .filter_map(|c| match c {
+                '\n' | '\r' | '\t' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+        self.value.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Delete the grapheme before the cursor
+    pub fn delete_before(&mut self) {
+        let start = self.previous_boundary();
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete the grapheme under the cursor
+    pub fn delete_after(&mut self) {
+        let end = self.next_boundary();
+        self.value.replace_range(self.cursor..end, "");
+    }
+
+    /// Delete the word before the cursor and the spaces after it, like
+    /// `Ctrl+w` in a shell
+    pub fn delete_word_before(&mut self) {
+        let start = self.previous_word_start();
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete everything before the cursor
+    pub fn delete_to_start(&mut self) {
+        self.value.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.previous_word_start();
+    }
+
+    pub fn move_word_right(&mut self) {
+        let rest = &self.value[self.cursor..];
+        let end = rest
+            .split_word_bound_indices()
+            .find(|(_, word)| !word.trim().is_empty())
+            .map_or(rest.len(), |(index, word)| index + word.len());
+        self.cursor += end;
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.value.len();
+    }
+
+    /// Where the cursor is drawn when the text is rendered into `area`
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let offset = self.value[self.scroll.min(self.cursor)..self.cursor].width() as u16;
+        Position::new(area.x + offset.min(area.width.saturating_sub(1)), area.y)
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.value[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.value[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    fn previous_word_start(&self) -> usize {
+        self.value[..self.cursor]
+            .split_word_bound_indices()
+            .rev()
+            .find(|(_, word)| !word.trim().is_empty())
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// Scroll horizontally so the cursor stays inside a field `width` columns wide
+    fn scroll_to_cursor(&mut self, width: usize) {
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        }
+        let mut graphemes = self.value[self.scroll..self.cursor].grapheme_indices(true);
+        while self.value[self.scroll..self.cursor].width() >= width.max(1) {
+            match graphemes.next() {
+                Some((_, grapheme)) => self.scroll += grapheme.len(),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Renders a [`TextInput`], scrolled so the cursor is visible. The caller
+/// draws the surrounding block and sets the terminal cursor with
+/// [`TextInput::cursor_position`].
+#[derive(Debug, Default, Clone)]
+pub struct InputField {
+    style: Style,
+}
+
+impl InputField {
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl StatefulWidget for InputField {
+    type State = TextInput;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut TextInput) {
+        if area.is_empty() {
+            return;
+        }
+        state.scroll_to_cursor(area.width as usize);
+        buf.set_style(area, self.style);
+        buf.set_stringn(
+            area.x,
+            area.y,
+            &state.value[state.scroll..],
+            area.width as usize,
+            self.style,
+        );
+    }
+}
@@ -0,0 +1,477 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Something the user can do with a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    NextRow,
+    PreviousRow,
+    ToggleTimer,
+    ContinueTimer,
+    AddTimer,
+    AddPastEntry,
+    EditTimer,
+    EditDescription,
+    DeleteTimer,
+    FilterProject,
+    FilterTag,
+    ShowStats,
+    Undo,
+    Redo,
+    ShowTrash,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    RestoreTimer,
+    PurgeTimer,
+    NextField,
+    Submit,
+    Cancel,
+    DeleteChar,
+    DeleteNextChar,
+    DeleteWord,
+    DeleteToStart,
+    CursorLeft,
+    CursorRight,
+    WordLeft,
+    WordRight,
+    CursorHome,
+    CursorEnd,
+    NextOption,
+    PreviousOption,
+    Confirm,
+    Deny,
+    ToggleButton,
+    PressButton,
+    ToggleRange,
+    /// A character typed into a dialog field, not bindable
+    #[serde(skip)]
+    Type(char),
+}
+
+impl Action {
+    /// Short description shown in the footer
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Quit => "Exit",
+            Action::NextRow => "Down",
+            Action::PreviousRow => "Up",
+            Action::ToggleTimer => "Start/Stop timer",
+            Action::ContinueTimer => "Continue timer",
+            Action::AddTimer => "Add timer",
+            Action::AddPastEntry => "Add past entry",
+            Action::EditTimer => "Edit timer",
+            Action::EditDescription => "Edit notes",
+            Action::DeleteTimer => "Delete timer",
+            Action::FilterProject => "Filter project",
+            Action::FilterTag => "Filter tag",
+            Action::ShowStats => "Statistics",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::ShowTrash => "Trash",
+            Action::Search => "Search",
+            Action::NextMatch => "Next match",
+            Action::PreviousMatch => "Previous match",
+            Action::RestoreTimer => "Restore",
+            Action::PurgeTimer => "Delete forever",
+            Action::NextField => "Next field",
+            Action::Submit => "Submit",
+            Action::Cancel => "Back",
+            Action::DeleteChar => "Delete character",
+            Action::DeleteNextChar => "Delete next character",
+            Action::DeleteWord => "Delete word",
+            Action::DeleteToStart => "Delete to start",
+            Action::CursorLeft => "Cursor left",
+            Action::CursorRight => "Cursor right",
+            Action::WordLeft => "Word left",
+            Action::WordRight => "Word right",
+            Action::CursorHome => "Start of field",
+            Action::CursorEnd => "End of field",
+            Action::NextOption => "Next project",
+            Action::PreviousOption => "Previous project",
+            Action::Confirm => "Yes",
+            Action::Deny => "No",
+            Action::ToggleButton => "Switch",
+            Action::PressButton => "Confirm",
+            Action::ToggleRange => "Last 7/30 days",
+            Action::Type(_) => "Type",
+        }
+    }
+
+    /// Cursor movement and deletes inside a field, left out of the footer
+    pub fn edits_text(&self) -> bool {
+        matches!(
+            self,
+            Action::DeleteNextChar
+                | Action::DeleteWord
+                | Action::DeleteToStart
+                | Action::CursorLeft
+                | Action::CursorRight
+                | Action::WordLeft
+                | Action::WordRight
+                | Action::CursorHome
+                | Action::CursorEnd
+        )
+    }
+}
+
+/// The group of screens a binding applies to. Each has its own section in
+/// the `[keys]` table of the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyContext {
+    Main,
+    /// The add, edit and past entry dialogs
+    Dialog,
+    Exit,
+    Stats,
+    Trash,
+    Delete,
+}
+
+/// A key together with its modifiers, written as e.g. `alt+i` or `esc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Whether the key types a character into a dialog field when it isn't
+    /// bound
+    pub fn types_character(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    /// Whether a key press triggers this binding. Shift is part of the
+    /// character for letter keys, so it is ignored for them.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        if let KeyCode::Char(_) = key.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        // A lone "+" is a key, not a separator
+        let (modifier_part, key) = match input.rsplit_once('+') {
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(""), "+"),
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", input),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifier_part.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier '{}' in '{}'", modifier, input)),
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match key.to_lowercase().strip_prefix('f').map(str::parse) {
+                        Some(Ok(n @ 1..=12)) => KeyCode::F(n),
+                        _ => return Err(format!("Unknown key '{}'", input)),
+                    },
+                }
+            }
+        };
+
+        // Terminals report shift+a as an upper case A
+        if let KeyCode::Char(c) = code
+            && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            modifiers.remove(KeyModifiers::SHIFT);
+            return Ok(KeyBinding {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers,
+            });
+        }
+
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The key bindings of every screen, in the order they are listed in the
+/// footer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, Vec<(Action, Vec<KeyBinding>)>>,
+}
+
+/// Actions of a context with the keys they are bound to by default
+type DefaultBindings = &'static [(Action, &'static [&'static str])];
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+        let defaults: [(KeyContext, DefaultBindings); 6] = [
+            (
+                KeyContext::Main,
+                &[
+                    (ToggleTimer, &["space"]),
+                    (ContinueTimer, &["r"]),
+                    (AddTimer, &["alt+i"]),
+                    (AddPastEntry, &["p"]),
+                    (EditTimer, &["e"]),
+                    (EditDescription, &["E"]),
+                    (DeleteTimer, &["d"]),
+                    (FilterProject, &["f"]),
+                    (FilterTag, &["t"]),
+                    (ShowStats, &["s"]),
+                    (Undo, &["u"]),
+                    (Redo, &["ctrl+r"]),
+                    (ShowTrash, &["T"]),
+                    (Search, &["/"]),
+                    (NextMatch, &["n"]),
+                    (PreviousMatch, &["N"]),
+                    (NextRow, &["j", "down"]),
+                    (PreviousRow, &["k", "up"]),
+                    (Quit, &["esc", "ctrl+c"]),
+                ],
+            ),
+            (
+                KeyContext::Dialog,
+                &[
+                    (NextField, &["tab"]),
+                    (Submit, &["enter"]),
+                    (Cancel, &["esc"]),
+                    (DeleteChar, &["backspace"]),
+                    (DeleteNextChar, &["delete"]),
+                    (DeleteWord, &["ctrl+w", "ctrl+backspace"]),
+                    (DeleteToStart, &["ctrl+u"]),
+                    (CursorLeft, &["left"]),
+                    (CursorRight, &["right"]),
+                    (WordLeft, &["ctrl+left", "alt+b"]),
+                    (WordRight, &["ctrl+right", "alt+f"]),
+                    (CursorHome, &["home", "ctrl+a"]),
+                    (CursorEnd, &["end", "ctrl+e"]),
+                    (NextOption, &["down"]),
+                    (PreviousOption, &["up"]),
+                ],
+            ),
+            (
+                KeyContext::Exit,
+                &[
+                    (Confirm, &["y", "ctrl+c"]),
+                    (Deny, &["n", "q"]),
+                    (ToggleButton, &["tab"]),
+                    (PressButton, &["enter"]),
+                ],
+            ),
+            (
+                KeyContext::Stats,
+                &[(ToggleRange, &["tab"]), (Cancel, &["esc", "q"])],
+            ),
+            (
+                KeyContext::Trash,
+                &[
+                    (RestoreTimer, &["r", "enter"]),
+                    (PurgeTimer, &["x"]),
+                    (NextRow, &["j", "down"]),
+                    (PreviousRow, &["k", "up"]),
+                    (Cancel, &["esc", "q"]),
+                ],
+            ),
+            (
+                KeyContext::Delete,
+                &[(Confirm, &["y", "enter"]), (Deny, &["n", "q", "esc"])],
+            ),
+        ];
+
+        let bindings = defaults
+            .iter()
+            .map(|(context, actions)| {
+                let actions = actions
+                    .iter()
+                    .map(|(action, keys)| {
+                        let keys = keys
+                            .iter()
+                            .map(|key| key.parse().expect("Invalid default key binding"))
+                            .collect();
+                        (*action, keys)
+                    })
+                    .collect();
+                (*context, actions)
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// The action bound to a key press in `context`, if any
+    pub fn action(&self, context: KeyContext, key: &KeyEvent) -> Option<Action> {
+        self.bindings(context)
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|binding| binding.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    /// The first key bound to `action`, as shown in help texts
+    pub fn key(&self, context: KeyContext, action: Action) -> Option<KeyBinding> {
+        self.bindings(context)
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, keys)| keys.first().copied())
+    }
+
+    /// All actions available in `context` with their keys
+    pub fn bindings(&self, context: KeyContext) -> &[(Action, Vec<KeyBinding>)] {
+        self.bindings.get(&context).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replace the keys of an action. Only actions that exist in `context`
+    /// can be rebound, and the keys must not already trigger another action
+    /// there. The keymap is left unchanged if they are rejected.
+    pub fn rebind(
+        &mut self,
+        context: KeyContext,
+        action: Action,
+        keys: Vec<KeyBinding>,
+    ) -> Result<(), String> {
+        let mut rebound = self.clone();
+        rebound.set_keys(context, action, keys)?;
+        rebound.check(context)?;
+        *self = rebound;
+        Ok(())
+    }
+
+    fn set_keys(
+        &mut self,
+        context: KeyContext,
+        action: Action,
+        keys: Vec<KeyBinding>,
+    ) -> Result<(), String> {
+        let binding = self
+            .bindings
+            .get_mut(&context)
+            .and_then(|actions| actions.iter_mut().find(|(a, _)| *a == action))
+            .ok_or_else(|| format!("Action {:?} can't be bound in {:?}", action, context))?;
+        binding.1 = keys;
+        Ok(())
+    }
+
+    /// Reject a key that triggers two actions in `context`, and dialog keys
+    /// that would stop a character from being typed into the fields
+    fn check(&self, context: KeyContext) -> Result<(), String> {
+        let bindings = self.bindings(context);
+        for (index, (action, keys)) in bindings.iter().enumerate() {
+            for key in keys {
+                if context == KeyContext::Dialog && key.types_character() {
+                    return Err(format!(
+                        "Key '{}' of {:?} types a character in dialogs, add ctrl+ or alt+",
+                        key, action
+                    ));
+                }
+                if let Some((other, _)) = bindings[index + 1..]
+                    .iter()
+                    .find(|(_, other_keys)| other_keys.contains(key))
+                {
+                    return Err(format!(
+                        "Key '{}' is bound to both {:?} and {:?} in {:?}",
+                        key, action, other, context
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One key or a list of keys
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The `[keys]` table only lists the actions that are rebound, everything
+/// else keeps its default keys
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let overrides = HashMap::<KeyContext, HashMap<Action, Keys>>::deserialize(deserializer)?;
+
+        // Keys are checked once every override is in place, so two actions
+        // can swap their keys
+        let mut keymap = Keymap::default();
+        for (context, actions) in &overrides {
+            for (action, keys) in actions {
+                let keys = match keys {
+                    Keys::One(key) => std::slice::from_ref(key),
+                    Keys::Many(keys) => keys.as_slice(),
+                };
+                let keys = keys
+                    .iter()
+                    .map(|key| key.parse())
+                    .collect::<Result<Vec<KeyBinding>, String>>()
+                    .map_err(serde::de::Error::custom)?;
+                keymap
+                    .set_keys(*context, *action, keys)
+                    .map_err(serde::de::Error::custom)?;
+            }
+        }
+        for context in overrides.keys() {
+            keymap.check(*context).map_err(serde::de::Error::custom)?;
+        }
+        Ok(keymap)
+    }
+}
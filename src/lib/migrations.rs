@@ -0,0 +1,169 @@
+use crate::lib::error::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, Transaction, params};
+
+type Migration = fn(&Transaction) -> Result<(), rusqlite::Error>;
+
+/// Ordered list of schema migrations. The position in this list (starting at 1)
+/// is the schema version stored in `PRAGMA user_version` once it has been
+/// applied. Only ever append to this list.
+const MIGRATIONS: &[Migration] = &[
+    create_timers_table,
+    create_timer_sessions_table,
+    drop_timer_duration_column,
+    create_projects_and_clients,
+    create_tags,
+    add_timer_deleted_at,
+    index_timer_sessions,
+];
+
+/// The schema version this binary expects
+pub fn latest_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+/// Read the schema version of an open database
+pub fn current_version(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Apply all pending migrations in a single transaction. Refuses to touch a
+/// database that was written by a newer version of the application.
+pub fn run(conn: &mut Connection) -> Result<()> {
+    let version = current_version(conn)?;
+    let latest = latest_version();
+
+    if version > latest {
+        return Err(Error::SchemaTooNew { version, latest });
+    }
+
+    let tx = conn.transaction()?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", index as i64 + 1)?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Version 1: the original schema. Databases created before migrations were
+/// introduced already have this table and report version 0.
+fn create_timers_table(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS timers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            start_time DATETIME NOT NULL,
+            duration INTEGER NOT NULL,
+            running BOOLEAN NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Version 2: one row per start/stop cycle. The accumulated duration of
+/// existing timers becomes a single session, and timers that were running keep
+/// running from now on.
+fn create_timer_sessions_table(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS timer_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timer_id INTEGER NOT NULL REFERENCES timers(id),
+            started_at DATETIME NOT NULL,
+            ended_at DATETIME
+        )",
+        [],
+    )?;
+
+    let mut stmt = tx.prepare(
+        "SELECT id, start_time, duration, running FROM timers
+        WHERE NOT EXISTS (SELECT 1 FROM timer_sessions WHERE timer_id = timers.id)",
+    )?;
+    let legacy = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, bool>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+    for (id, start_time, duration, running) in legacy {
+        let started_at = DateTime::parse_from_rfc3339(&start_time)
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, e.into())
+            })?
+            .to_utc();
+        let ended_at = started_at + Duration::seconds(duration);
+        tx.execute(
+            "INSERT INTO timer_sessions (timer_id, started_at, ended_at) VALUES (?, ?, ?)",
+            params![id, started_at.to_rfc3339(), ended_at.to_rfc3339()],
+        )?;
+        if running {
+            tx.execute(
+                "INSERT INTO timer_sessions (timer_id, started_at, ended_at) VALUES (?, ?, NULL)",
+                params![id, Utc::now().to_rfc3339()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Version 3: durations are computed from sessions, the old total is unused
+fn drop_timer_duration_column(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute("ALTER TABLE timers DROP COLUMN duration", [])?;
+    Ok(())
+}
+
+/// Version 4: timers can belong to a project, which optionally belongs to a client
+fn create_projects_and_clients(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute_batch(
+        "CREATE TABLE clients (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            client_id INTEGER REFERENCES clients(id),
+            name TEXT NOT NULL
+        );
+        ALTER TABLE timers ADD COLUMN project_id INTEGER REFERENCES projects(id);",
+    )?;
+    Ok(())
+}
+
+/// Version 5: free-form tags, shared between timers
+fn create_tags(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute_batch(
+        "CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE timer_tags (
+            timer_id INTEGER NOT NULL REFERENCES timers(id),
+            tag_id INTEGER NOT NULL REFERENCES tags(id),
+            PRIMARY KEY (timer_id, tag_id)
+        );",
+    )?;
+    Ok(())
+}
+
+/// Version 6: deleted timers are kept in the trash until they are purged
+fn add_timer_deleted_at(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute("ALTER TABLE timers ADD COLUMN deleted_at DATETIME", [])?;
+    Ok(())
+}
+
+/// Version 7: sessions are looked up by timer when a single timer is loaded
+fn index_timer_sessions(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE INDEX timer_sessions_timer_id ON timer_sessions (timer_id)",
+        [],
+    )?;
+    Ok(())
+}
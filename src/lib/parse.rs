@@ -0,0 +1,225 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// Parse a user supplied date relative to `today`. Accepts `today`,
+/// `yesterday`, a number of days ago like `7d`, or an ISO date (`2025-01-28`).
+pub fn parse_date(input: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let input = input.trim().to_lowercase();
+    let invalid = || {
+        format!(
+            "Invalid date '{}', expected today, yesterday, <n>d or YYYY-MM-DD",
+            input
+        )
+    };
+
+    match input.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return today.pred_opt().ok_or_else(invalid),
+        _ => {}
+    }
+
+    if let Some(days) = input.strip_suffix('d')
+        && let Ok(days) = days.parse::<i64>()
+    {
+        // Too many days to go back from today is as invalid as a typo
+        return Duration::try_days(days)
+            .and_then(|days| today.checked_sub_signed(days))
+            .ok_or_else(invalid);
+    }
+
+    NaiveDate::parse_from_str(&input, "%Y-%m-%d").map_err(|_| invalid())
+}
+
+/// A duration typed into the edit dialog: either a new total or a relative
+/// adjustment of the current one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationChange {
+    Set(Duration),
+    Add(Duration),
+}
+
+impl DurationChange {
+    /// The new duration, or `None` if an adjustment overflows
+    pub fn apply(&self, current: Duration) -> Option<Duration> {
+        match self {
+            DurationChange::Set(duration) => Some(*duration),
+            DurationChange::Add(delta) => current.checked_add(delta),
+        }
+    }
+}
+
+/// Parse a duration like `1h30m`, `45m`, `90s` or `01:30:00`. A leading `+`
+/// or `-` makes it an adjustment of the current duration.
+pub fn parse_duration_change(input: &str) -> Result<DurationChange, String> {
+    let input = input.trim();
+
+    if let Some(rest) = input.strip_prefix('+') {
+        return parse_duration(rest).map(DurationChange::Add);
+    }
+    if let Some(rest) = input.strip_prefix('-') {
+        return parse_duration(rest).map(|duration| DurationChange::Add(-duration));
+    }
+    parse_duration(input).map(DurationChange::Set)
+}
+
+/// Parse an unsigned duration like `1h30m`, `45m`, `90s`, `01:30` or `01:30:00`
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim().to_lowercase();
+    let invalid = || {
+        format!(
+            "Invalid duration '{}', expected e.g. 1h30m, 45m or 01:30:00",
+            input
+        )
+    };
+
+    if input.contains(':') {
+        let parts = input
+            .split(':')
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<u32>, String>>()?;
+        return match parts[..] {
+            [hours, minutes] if minutes < 60 => {
+                Ok(Duration::hours(hours.into()) + Duration::minutes(minutes.into()))
+            }
+            [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+                Ok(Duration::hours(hours.into())
+                    + Duration::minutes(minutes.into())
+                    + Duration::seconds(seconds.into()))
+            }
+            _ => Err(invalid()),
+        };
+    }
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let value: i64 = number.parse().map_err(|_| invalid())?;
+        let part = match c {
+            'h' => Duration::try_hours(value),
+            'm' => Duration::try_minutes(value),
+            's' => Duration::try_seconds(value),
+            _ => return Err(invalid()),
+        };
+        total = part
+            .and_then(|part| total.checked_add(&part))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+
+    // A bare number is taken as minutes
+    if !number.is_empty() {
+        if total != Duration::zero() {
+            return Err(invalid());
+        }
+        total =
+            Duration::try_minutes(number.parse().map_err(|_| invalid())?).ok_or_else(invalid)?;
+    }
+    if input.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// The local date `time` falls on, which is what `today` means to the user
+pub fn local_date(time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&Local).date_naive()
+}
+
+/// Convert a wall-clock time in the local time zone to UTC. An ambiguous time
+/// when the clocks go back is the earlier one; a time skipped when they go
+/// forward is moved past the gap. `None` if it is out of range.
+pub fn local_to_utc(date_time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    [date_time, date_time.checked_add_signed(Duration::hours(1))?]
+        .iter()
+        .find_map(|date_time| Local.from_local_datetime(date_time).earliest())
+        .map(|date_time| date_time.with_timezone(&Utc))
+}
+
+/// Parse a start time typed in local time. `HH:MM` keeps the date of
+/// `current`, `YYYY-MM-DD HH:MM` sets both, and `+15m` / `-10m` shift
+/// `current`.
+pub fn parse_start_time(input: &str, current: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    let invalid = || {
+        format!(
+            "Invalid start time '{}', expected HH:MM, YYYY-MM-DD HH:MM or +/-15m",
+            input
+        )
+    };
+
+    if input.starts_with('+') || input.starts_with('-') {
+        return match parse_duration_change(input)? {
+            DurationChange::Add(delta) => current.checked_add_signed(delta).ok_or_else(invalid),
+            DurationChange::Set(_) => unreachable!("signed input is always an adjustment"),
+        };
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(input, format) {
+            return local_to_utc(date_time).ok_or_else(invalid);
+        }
+    }
+    for format in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(input, format) {
+            return local_to_utc(local_date(current).and_time(time)).ok_or_else(invalid);
+        }
+    }
+
+    Err(invalid())
+}
+
+/// Split tag input such as `#meeting, #review` into sorted, lowercase tag
+/// names without the leading `#`
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|tag| tag.trim_start_matches('#').to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Resolve the start and duration of an entry for time that was already spent.
+/// Without a start time the entry ends `now`; a start time without a date is
+/// taken to be today. Dates and times are local. The entry must lie entirely
+/// in the past.
+pub fn parse_past_entry(
+    date: Option<&str>,
+    start: Option<&str>,
+    duration: &str,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, Duration), String> {
+    let duration = parse_duration(duration)?;
+    if duration <= Duration::zero() {
+        return Err("Duration must be longer than zero".to_string());
+    }
+
+    let too_long = || "Duration is too long".to_string();
+    let start_time = match (date, start) {
+        (None, None) => now.checked_sub_signed(duration).ok_or_else(too_long)?,
+        (Some(_), None) => return Err("A start time is required with a date".to_string()),
+        (date, Some(start)) => {
+            let today = local_date(now);
+            let date = match date {
+                Some(date) => parse_date(date, today)?,
+                None => today,
+            };
+            let midnight = local_to_utc(date.and_time(NaiveTime::MIN))
+                .ok_or_else(|| format!("Invalid date '{}'", date))?;
+            parse_start_time(start, midnight)?
+        }
+    };
+
+    let end_time = start_time
+        .checked_add_signed(duration)
+        .ok_or_else(too_long)?;
+    if end_time > now {
+        return Err("The entry cannot end in the future".to_string());
+    }
+    Ok((start_time, duration))
+}
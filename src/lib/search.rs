@@ -0,0 +1,52 @@
+use crate::lib::app::Timer;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// A search over timer names and descriptions, typed after `/`. Queries are
+/// regular expressions, or searched for literally if they aren't valid ones.
+/// Like vim's `smartcase`, the search ignores case unless the query contains
+/// an upper case letter.
+#[derive(Debug, Clone)]
+pub struct Search {
+    query: String,
+    regex: Regex,
+}
+
+impl Search {
+    /// `None` for an empty query, which shows all timers again
+    pub fn new(query: &str) -> Option<Search> {
+        if query.is_empty() {
+            return None;
+        }
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        let build = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+        };
+        let regex = build(query)
+            .or_else(|_| build(&regex::escape(query)))
+            .ok()?;
+        Some(Search {
+            query: query.to_string(),
+            regex,
+        })
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn is_match(&self, timer: &Timer) -> bool {
+        self.regex.is_match(&timer.name) || self.regex.is_match(&timer.description)
+    }
+
+    /// Byte ranges of the matches in `text`, for highlighting
+    pub fn match_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.range())
+            .collect()
+    }
+}
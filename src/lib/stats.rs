@@ -0,0 +1,79 @@
+use crate::lib::db::Db;
+use crate::lib::error::Result;
+use crate::lib::parse;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+
+/// The period covered by the statistics screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsRange {
+    Week,
+    Month,
+}
+
+impl StatsRange {
+    pub fn days(&self) -> i64 {
+        match self {
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+        }
+    }
+
+    pub fn toggle(&self) -> StatsRange {
+        match self {
+            StatsRange::Week => StatsRange::Month,
+            StatsRange::Month => StatsRange::Week,
+        }
+    }
+}
+
+/// Totals shown on the statistics screen. Days and weeks without any tracked
+/// time are included with a zero total so the charts have no gaps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub range: StatsRange,
+    pub daily: Vec<(NaiveDate, Duration)>,
+    pub weekly: Vec<(NaiveDate, Duration)>,
+    pub by_name: Vec<(String, Duration)>,
+}
+
+impl Stats {
+    /// Load the totals of the last `range.days()` local days, including today
+    pub fn load(db: &Db, range: StatsRange, now: DateTime<Utc>) -> Result<Stats> {
+        let today = parse::local_date(now);
+        let since = today - Duration::days(range.days() - 1);
+        let first_week = since - Duration::days(since.weekday().num_days_from_monday() as i64);
+
+        Ok(Stats {
+            range,
+            daily: fill_gaps(db.daily_totals(since, now)?, since, today, 1),
+            weekly: fill_gaps(db.weekly_totals(first_week, now)?, first_week, today, 7),
+            by_name: db.totals_by_name(since, now)?,
+        })
+    }
+
+    pub fn total(&self) -> Duration {
+        self.daily.iter().map(|(_, total)| *total).sum()
+    }
+}
+
+/// One entry every `step` days from `first` up to `last`, taking the totals
+/// from `totals` where there are any
+fn fill_gaps(
+    totals: Vec<(NaiveDate, Duration)>,
+    first: NaiveDate,
+    last: NaiveDate,
+    step: i64,
+) -> Vec<(NaiveDate, Duration)> {
+    first
+        .iter_days()
+        .step_by(step as usize)
+        .take_while(|date| *date <= last)
+        .map(|date| {
+            let total = totals
+                .iter()
+                .find(|(day, _)| *day == date)
+                .map_or(Duration::zero(), |(_, total)| *total);
+            (date, total)
+        })
+        .collect()
+}
@@ -23,6 +23,12 @@ pub struct Throbber {
     state: ThrobberState,
 }
 
+impl Default for Throbber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Throbber {
     pub fn new() -> Throbber {
         Throbber {
@@ -1,12 +1,20 @@
 use crate::lib::app;
-use crate::lib::app::{App, CurrentScreen, CurrentlyEditing};
+use crate::lib::app::{App, CurrentScreen, CurrentlyEditing, Status};
+use crate::lib::config::{Config, DeleteConfirmation, Theme};
+use crate::lib::input::InputField;
+use crate::lib::keymap::{Action, KeyContext};
+use crate::lib::search::Search;
+use crate::lib::stats::{Stats, StatsRange};
 use crate::lib::throbber::Throbber;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::prelude::Direction;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table};
+use ratatui::widgets::{
+    Bar, BarChart, BarGroup, Block, Borders, Cell, Clear, Paragraph, Row, Table, Wrap,
+};
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -29,30 +37,35 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 fn create_rows_with_subheaders(
-    timers: &Vec<app::Timer>,
+    timers: &[&app::Timer],
+    projects: &[app::Project],
     throbber: &Throbber,
+    config: &Config,
+    search: Option<&Search>,
+    now: DateTime<Utc>,
 ) -> (Vec<Row<'static>>, Vec<bool>) {
     let mut rows = Vec::new();
     let mut selectable_rows = Vec::new();
 
-    if timers.is_empty() {
-        return (rows, selectable_rows);
-    }
-
-    let mut current_date = timers.first().unwrap().formatted_date();
-    rows.push(create_row_for_date(current_date.clone()));
-    selectable_rows.push(false);
-
-    for (i, timer) in timers.iter().enumerate() {
-        let is_last = i == timers.len() - 1;
-        if current_date != timer.formatted_date() {
-            current_date = timer.formatted_date();
-            rows.push(create_row_for_date(current_date.clone()));
-            selectable_rows.push(false);
-            rows.push(create_row_for_timer(timer, is_last, throbber));
-            selectable_rows.push(true);
-        } else {
-            rows.push(create_row_for_timer(timer, is_last, throbber));
+    for day in timers.chunk_by(|a, b| a.local_date() == b.local_date()) {
+        let total = day.iter().map(|timer| timer.duration(now)).sum();
+        let date = day[0]
+            .start_time
+            .with_timezone(&Local)
+            .format(&config.date_format)
+            .to_string();
+        rows.push(create_row_for_date(date, total, config));
+        selectable_rows.push(false);
+
+        for timer in day {
+            rows.push(create_row_for_timer(
+                timer,
+                projects,
+                throbber,
+                &config.theme,
+                search,
+                now,
+            ));
             selectable_rows.push(true);
         }
     }
@@ -60,27 +73,77 @@ fn create_rows_with_subheaders(
     (rows, selectable_rows)
 }
 
-fn create_row_for_date(date: String) -> Row<'static> {
+/// A text progress bar of the day's total against the daily target
+fn daily_progress(total: Duration, target: Duration, theme: &Theme) -> Span<'static> {
+    const WIDTH: i64 = 20;
+    let filled = (total.num_seconds() * WIDTH / target.num_seconds().max(1)).clamp(0, WIDTH);
+    let percent = total.num_seconds() * 100 / target.num_seconds().max(1);
+    let style = if total >= target {
+        Style::default().fg(theme.target_reached)
+    } else {
+        Style::default()
+    };
+    Span::styled(
+        format!(
+            "{}{} {}% of {}",
+            "█".repeat(filled as usize),
+            "░".repeat((WIDTH - filled) as usize),
+            percent,
+            format_hours(target)
+        ),
+        style,
+    )
+}
+
+fn create_row_for_date(date: String, total: Duration, config: &Config) -> Row<'static> {
+    let theme = &config.theme;
     Row::new(vec![
         Cell::from(date),
         Cell::from(""),
-        Cell::from(""),
+        Cell::from(config.daily_target.map_or(Span::from(""), |target| {
+            daily_progress(total, target, theme)
+        })),
+        Cell::from(app::format_duration(total)),
         Cell::from(""),
     ])
     .style(
         Style::default()
-            .bg(Color::Gray)
-            .fg(Color::White)
+            .bg(theme.date_row_bg)
+            .fg(theme.date_row_fg)
             .add_modifier(Modifier::BOLD),
     )
 }
 
-fn create_row_for_timer(timer: &app::Timer, is_last: bool, throbber: &Throbber) -> Row<'static> {
+fn create_row_for_timer(
+    timer: &app::Timer,
+    projects: &[app::Project],
+    throbber: &Throbber,
+    theme: &Theme,
+    search: Option<&Search>,
+    now: DateTime<Utc>,
+) -> Row<'static> {
+    let project = projects
+        .iter()
+        .find(|project| Some(project.id) == timer.project_id)
+        .map(app::Project::display_name)
+        .unwrap_or_default();
+    let match_style = Style::default().bg(theme.search_match).fg(Color::Black);
+    // Longer notes are shown in the detail pane
+    let mut lines = timer.description.lines();
+    let mut description = highlight(lines.next().unwrap_or_default(), search, match_style);
+    if lines.next().is_some() {
+        description.push(Span::from(" …"));
+    }
+    for tag in &timer.tags {
+        description.push(Span::from(" "));
+        description.push(tag_chip(tag, &theme.tag_colors));
+    }
     Row::new(vec![
-        Cell::from(timer.name.clone()),
-        Cell::from(timer.description.clone()),
-        Cell::from(timer.formatted_duration().clone()),
-        Cell::from(if is_last {
+        Cell::from(Line::from(highlight(&timer.name, search, match_style))),
+        Cell::from(project),
+        Cell::from(Line::from(description)),
+        Cell::from(timer.formatted_duration(now)),
+        Cell::from(if timer.running {
             Span::from(throbber.get_state_string().to_string() + " ")
         } else {
             Span::from("")
@@ -88,6 +151,229 @@ fn create_row_for_timer(timer: &app::Timer, is_last: bool, throbber: &Throbber)
     ])
 }
 
+/// `text` with the matches of the search highlighted
+fn highlight(text: &str, search: Option<&Search>, style: Style) -> Vec<Span<'static>> {
+    let ranges = search.map_or_else(Vec::new, |search| search.match_ranges(text));
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for range in ranges {
+        spans.push(Span::from(text[end..range.start].to_string()));
+        spans.push(Span::styled(text[range.clone()].to_string(), style));
+        end = range.end;
+    }
+    spans.push(Span::from(text[end..].to_string()));
+    spans
+}
+
+/// The full description of a timer, wrapped to the width of the pane
+fn detail_pane(timer: &app::Timer) -> Paragraph<'static> {
+    Paragraph::new(timer.description.clone())
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Notes - {}", timer.name)),
+        )
+}
+
+/// A tag rendered as a colored chip. The color is derived from the name so a
+/// tag looks the same in every row.
+fn tag_chip(tag: &str, colors: &[Color]) -> Span<'static> {
+    let hash = tag.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+    let color = colors
+        .get(hash % colors.len().max(1))
+        .copied()
+        .unwrap_or(Color::Gray);
+    Span::styled(
+        format!(" #{} ", tag),
+        Style::default().bg(color).fg(Color::Black),
+    )
+}
+
+/// `<key> Action` for every bound action of `context`, as shown in the footer
+fn key_hint(app: &App, context: KeyContext) -> String {
+    let double_press = app.config.delete_confirmation == DeleteConfirmation::DoublePress;
+    app.config
+        .keys
+        .bindings(context)
+        .iter()
+        .filter(|(action, _)| !action.edits_text())
+        .filter_map(|(action, keys)| {
+            let key = keys.first()?;
+            Some(if *action == Action::DeleteTimer && double_press {
+                format!("<{}{}> {}", key, key, action.label())
+            } else {
+                format!("<{}> {}", key, action.label())
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// The first key bound to `action`, for help texts
+fn key_name(app: &App, context: KeyContext, action: Action) -> String {
+    app.config
+        .keys
+        .key(context, action)
+        .map_or_else(|| "unbound".to_string(), |key| key.to_string())
+}
+
+/// A total as `HH:MM`, which can exceed 24 hours
+fn format_hours(total: Duration) -> String {
+    format!("{:02}:{:02}", total.num_hours(), total.num_minutes() % 60)
+}
+
+/// A vertical bar per day or week, sized to fill `area`
+fn date_chart<'a>(
+    title: String,
+    totals: &[(NaiveDate, Duration)],
+    label_format: &str,
+    color: Color,
+    area: Rect,
+) -> BarChart<'a> {
+    let bars: Vec<Bar> = totals
+        .iter()
+        .map(|(date, total)| {
+            Bar::default()
+                .value(total.num_minutes().max(0) as u64)
+                .text_value(format!("{:.1}", total.num_minutes() as f64 / 60.0))
+                .label(Line::from(date.format(label_format).to_string()))
+        })
+        .collect();
+    let width = area.width.saturating_sub(2) / (bars.len().max(1) as u16);
+
+    BarChart::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(width.saturating_sub(1).max(1))
+        .bar_gap(1)
+        .bar_style(Style::default().fg(color))
+        .value_style(Style::default().fg(Color::Black).bg(color))
+}
+
+fn render_stats(frame: &mut Frame, stats: &Stats, theme: &Theme, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let bottom_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(chunks[1]);
+
+    let label_format = match stats.range {
+        StatsRange::Week => "%a",
+        StatsRange::Month => "%d",
+    };
+    let daily = date_chart(
+        format!(
+            "Hours per day - last {} days ({} total)",
+            stats.range.days(),
+            format_hours(stats.total())
+        ),
+        &stats.daily,
+        label_format,
+        theme.daily_chart,
+        chunks[0],
+    );
+    frame.render_widget(daily, chunks[0]);
+
+    let weekly = date_chart(
+        "Hours per week".to_string(),
+        &stats.weekly,
+        "%d-%m",
+        theme.daily_chart,
+        bottom_chunks[0],
+    );
+    frame.render_widget(weekly, bottom_chunks[0]);
+
+    let bars: Vec<Bar> = stats
+        .by_name
+        .iter()
+        .map(|(name, total)| {
+            Bar::default()
+                .value(total.num_minutes().max(0) as u64)
+                .text_value(format_hours(*total))
+                .label(Line::from(name.clone()))
+        })
+        .collect();
+    let by_name = BarChart::default()
+        .block(Block::default().title("By timer").borders(Borders::ALL))
+        .direction(Direction::Horizontal)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(theme.name_chart))
+        .value_style(Style::default().fg(Color::Black).bg(theme.name_chart));
+    frame.render_widget(by_name, bottom_chunks[1]);
+}
+
+/// The deleted timers, most recently deleted first
+fn render_trash(frame: &mut Frame, app: &mut App, area: Rect) {
+    let now = app.clock.now();
+    let deleted_format = format!("{} %H:%M", app.config.date_format);
+    let rows: Vec<Row> = app
+        .trash
+        .iter()
+        .map(|timer| {
+            Row::new(vec![
+                Cell::from(timer.name.clone()),
+                Cell::from(timer.description.clone()),
+                Cell::from(
+                    timer
+                        .start_time
+                        .with_timezone(&Local)
+                        .format(&app.config.date_format)
+                        .to_string(),
+                ),
+                Cell::from(timer.formatted_duration(now)),
+                Cell::from(
+                    timer
+                        .deleted_at
+                        .map(|deleted_at| {
+                            deleted_at
+                                .with_timezone(&Local)
+                                .format(&deleted_format)
+                                .to_string()
+                        })
+                        .unwrap_or_default(),
+                ),
+            ])
+        })
+        .collect();
+
+    let mut title = format!("Trash ({})", app.trash.len());
+    if app.config.trash_retention_days > 0 {
+        title += &format!(" - emptied after {} days", app.config.trash_retention_days);
+    }
+    let table = Table::new(
+        rows,
+        &[
+            Constraint::Percentage(15),
+            Constraint::Fill(1),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(18),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            Cell::from("Name"),
+            Cell::from("Description"),
+            Cell::from("Date"),
+            Cell::from("Duration"),
+            Cell::from("Deleted"),
+        ])
+        .bottom_margin(1),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_stateful_widget(table, area, &mut app.trash_state);
+}
+
 pub fn ui(frame: &mut Frame, app: &mut App) {
     // Create the layout sections.
     let chunks = Layout::default()
@@ -109,15 +395,39 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
 
     // render table in chunk[1]
 
-    let (rows, selectable_rows) = create_rows_with_subheaders(&app.timers, &app.throbber);
+    let visible_timers: Vec<&app::Timer> = app
+        .visible_timer_indices()
+        .into_iter()
+        .map(|index| &app.timers[index])
+        .collect();
+    let (rows, selectable_rows) = create_rows_with_subheaders(
+        &visible_timers,
+        &app.projects,
+        &app.throbber,
+        &app.config,
+        app.search.as_ref(),
+        app.clock.now(),
+    );
     app.selectable_rows = selectable_rows;
 
+    let mut table_title = "Timers".to_string();
+    if let Some(project) = app.project_name(app.project_filter) {
+        table_title += &format!(" - {}", project);
+    }
+    if let Some(tag) = &app.tag_filter {
+        table_title += &format!(" - #{}", tag);
+    }
+    if let Some(search) = &app.search {
+        table_title += &format!(" - /{}", search.query());
+    }
+
     let selected_row_style = Style::default().add_modifier(Modifier::REVERSED);
 
     let table = Table::new(
         rows,
         &[
             Constraint::Percentage(10),
+            Constraint::Percentage(20),
             Constraint::Fill(1),
             Constraint::Length(8),
             Constraint::Length(2),
@@ -126,6 +436,7 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
     .header(
         Row::new(vec![
             Cell::from("Name"),
+            Cell::from("Project"),
             Cell::from("Description"),
             Cell::from("Duration"),
             Cell::from(""),
@@ -138,30 +449,65 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
         Block::default()
             .borders(Borders::ALL)
             .style(Style::default())
-            .title("Timers"),
+            .title(table_title),
     );
 
     frame.render_widget(Clear, chunks[1]);
-    frame.render_stateful_widget(table, chunks[1], &mut app.state);
-    // Footer
-
-    let current_keys_hint = {
-        match &app.current_screen {
-            CurrentScreen::Main => Span::styled(
-                "<space> Start/Stop timer | <Alt + i> Add timer | <e> Edit timer | <dd> Delete timer | <j> Down | <k> Up | <Esc> Exit",
-                Style::default(),
-            ),
-            CurrentScreen::Exit => Span::styled("<y> Yes | <n> No", Style::default()),
-            CurrentScreen::Add | CurrentScreen::Edit => {
-                Span::styled("<Tab> Next field | <Enter> Submit", Style::default())
-            }
+    match (&app.current_screen, &app.stats) {
+        (CurrentScreen::Stats, Some(stats)) => {
+            render_stats(frame, stats, &app.config.theme, chunks[1])
         }
-    };
+        (CurrentScreen::Trash, _) => render_trash(frame, app, chunks[1]),
+        _ => match app
+            .selected_timer()
+            .filter(|timer| timer.description.contains('\n'))
+        {
+            Some(timer) => {
+                let [table_area, detail_area] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Percentage(35)])
+                        .areas(chunks[1]);
+                frame.render_widget(detail_pane(timer), detail_area);
+                frame.render_stateful_widget(table, table_area, &mut app.state);
+            }
+            None => frame.render_stateful_widget(table, chunks[1], &mut app.state),
+        },
+    }
+    // Footer
 
-    let key_notes_footer =
-        Paragraph::new(Line::from(current_keys_hint)).block(Block::default().borders(Borders::ALL));
+    let current_keys_hint = Span::styled(
+        key_hint(app, app.current_screen.key_context()),
+        Style::default(),
+    );
 
-    frame.render_widget(key_notes_footer, chunks[2]);
+    // Notices and errors that didn't stop the app are shown in the footer's border
+    let mut footer_block = Block::default().borders(Borders::ALL);
+    match &app.status {
+        Some(Status::Info(message)) => {
+            footer_block = footer_block.title(format!(" {} ", message));
+        }
+        Some(Status::Error(message)) => {
+            footer_block = footer_block.title(Span::styled(
+                format!(" {} ", message),
+                Style::default().fg(app.config.theme.error),
+            ));
+        }
+        None => {}
+    }
+    if let CurrentScreen::Search = app.current_screen {
+        // The search bar replaces the key hints while a search is typed
+        let inner = footer_block.inner(chunks[2]);
+        frame.render_widget(footer_block.title(" Search "), chunks[2]);
+        let [prompt_area, input_area] =
+            Layout::horizontal([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        frame.render_widget(Paragraph::new("/"), prompt_area);
+        frame.render_stateful_widget(InputField::default(), input_area, &mut app.search_input);
+        if !input_area.is_empty() {
+            frame.set_cursor_position(app.search_input.cursor_position(input_area));
+        }
+    } else {
+        let key_notes_footer = Paragraph::new(Line::from(current_keys_hint)).block(footer_block);
+        frame.render_widget(key_notes_footer, chunks[2]);
+    }
 
     if let CurrentScreen::Exit = app.current_screen {
         frame.render_widget(Clear, frame.area()); //this clears the entire screen and anything already drawn
@@ -243,60 +589,169 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
             .borders(Borders::ALL)
             .style(Style::default());
 
-        let help_text = Paragraph::new("<Tab> Switch | <Enter> Confirm")
-            .alignment(ratatui::layout::Alignment::Center)
-            .block(help_block)
-            .style(Style::default());
+        let help_text = Paragraph::new(format!(
+            "<{}> Switch | <{}> Confirm",
+            key_name(app, KeyContext::Exit, Action::ToggleButton),
+            key_name(app, KeyContext::Exit, Action::PressButton)
+        ))
+        .alignment(ratatui::layout::Alignment::Center)
+        .block(help_block)
+        .style(Style::default());
 
         frame.render_widget(help_text, help_area);
     }
 
-    if let CurrentScreen::Edit | CurrentScreen::Add = app.current_screen {
+    if let CurrentScreen::ConfirmDelete = app.current_screen
+        && let Some(selected) = app.state.selected()
+        && let Some(timer_index) = app.get_timer_index_from_selection(selected)
+    {
+        let area = Rect {
+            x: (frame.area().width.saturating_sub(50)) / 2,
+            y: (frame.area().height.saturating_sub(5)) / 2,
+            width: 50.min(frame.area().width),
+            height: 5.min(frame.area().height),
+        };
+        let question = Paragraph::new(format!(
+            "Move '{}' to the trash?\nIt can be restored from there.",
+            app.timers[timer_index].name
+        ))
+        .alignment(ratatui::layout::Alignment::Center)
+        .block(
+            Block::default()
+                .title("Delete timer")
+                .borders(Borders::ALL)
+                .style(Style::default()),
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(question, area);
+    }
+
+    if let CurrentScreen::Edit | CurrentScreen::Add | CurrentScreen::AddPast = app.current_screen {
         frame.render_widget(Clear, frame.area()); //this clears the entire screen and anything already drawn
-        let popup_block = Block::default()
-            .borders(Borders::NONE)
-            .style(Style::default());
 
-        let area = centered_rect(60, 35, frame.area());
-        frame.render_widget(popup_block, area);
+        let mut fields = vec![
+            ("Name".to_string(), CurrentlyEditing::Name),
+            ("Description".to_string(), CurrentlyEditing::Description),
+            (
+                "Project (Client / Project)".to_string(),
+                CurrentlyEditing::Project,
+            ),
+            ("Tags".to_string(), CurrentlyEditing::Tags),
+        ];
+
+        if let CurrentScreen::Edit = app.current_screen
+            && let Some(selected) = app.state.selected()
+            && let Some(timer_index) = app.get_timer_index_from_selection(selected)
+        {
+            let timer = &app.timers[timer_index];
+            fields.push((
+                format!(
+                    "Start ({})",
+                    timer
+                        .start_time
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M")
+                ),
+                CurrentlyEditing::StartTime,
+            ));
+            fields.push((
+                format!(
+                    "Duration ({}, e.g. 1h30m or +15m)",
+                    timer.formatted_duration(app.clock.now())
+                ),
+                CurrentlyEditing::Duration,
+            ));
+        }
+
+        if let CurrentScreen::AddPast = app.current_screen {
+            fields.push(("Date (today)".to_string(), CurrentlyEditing::Date));
+            fields.push(("Start".to_string(), CurrentlyEditing::StartTime));
+            fields.push(("Duration".to_string(), CurrentlyEditing::Duration));
+        }
 
-        // Split the area into main content and help box
+        // Two fields per row, followed by an error line and the help box
+        let field_rows = fields.len().div_ceil(2) as u16;
+        let centered = centered_rect(60, 100, frame.area());
+        let height = (field_rows * 3 + 6).min(centered.height);
+        let area = Rect {
+            y: centered.y + (centered.height - height) / 2,
+            height,
+            ..centered
+        };
+
+        let mut constraints = vec![Constraint::Length(3); field_rows as usize];
+        constraints.push(Constraint::Length(1));
+        constraints.push(Constraint::Length(3));
         let main_chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
-            .constraints([Constraint::Percentage(70), Constraint::Length(3)])
+            .constraints(constraints)
             .split(area);
 
-        let popup_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(main_chunks[0]);
-
-        let mut name_block = Block::default().title("Name").borders(Borders::ALL);
-        let mut desc_block = Block::default().title("Description").borders(Borders::ALL);
-
         let active_style = Style::default().add_modifier(Modifier::REVERSED);
-
-        match app.currently_editing {
-            Some(CurrentlyEditing::Name) => name_block = name_block.style(active_style),
-            Some(CurrentlyEditing::Description) => desc_block = desc_block.style(active_style),
-            None => {
-                name_block = name_block.style(active_style);
+        let active_field = app.currently_editing.unwrap_or(CurrentlyEditing::Name);
+
+        for (row, pair) in fields.chunks(2).enumerate() {
+            let popup_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(main_chunks[row]);
+
+            for (column, (title, field)) in pair.iter().enumerate() {
+                let mut block = Block::default().title(title.clone()).borders(Borders::ALL);
+                let mut input = InputField::default();
+                if *field == active_field {
+                    block = block.style(active_style);
+                    input = input.style(active_style);
+                }
+                let inner = block.inner(popup_chunks[column]);
+                frame.render_widget(block, popup_chunks[column]);
+                let value = app.input_mut(*field);
+                frame.render_stateful_widget(input, inner, value);
+                if *field == active_field && !inner.is_empty() {
+                    frame.set_cursor_position(value.cursor_position(inner));
+                }
             }
-        };
-
-        let key_text = Paragraph::new(app.name_input.clone()).block(name_block);
-        frame.render_widget(key_text, popup_chunks[0]);
+        }
 
-        let value_text = Paragraph::new(app.description_input.clone()).block(desc_block);
-        frame.render_widget(value_text, popup_chunks[1]);
+        if let Some(error) = &app.input_error {
+            let error_text =
+                Paragraph::new(error.clone()).style(Style::default().fg(app.config.theme.error));
+            frame.render_widget(error_text, main_chunks[field_rows as usize]);
+        }
 
         let help_block = Block::default()
             .borders(Borders::ALL)
             .style(Style::default());
 
-        let help_paragraph =
-            Paragraph::new("<Enter> Save | <Tab> Switch field | <Esc> Back").block(help_block);
-        frame.render_widget(help_paragraph, main_chunks[1]);
+        let adding_past = matches!(app.current_screen, CurrentScreen::AddPast);
+        let key = |action| key_name(app, KeyContext::Dialog, action);
+        let help_text = match app.currently_editing {
+            Some(CurrentlyEditing::Project) => &format!(
+                "<{}>/<{}> Existing projects | A new name creates the project",
+                key(Action::PreviousOption),
+                key(Action::NextOption)
+            ),
+            Some(CurrentlyEditing::Tags) => "Separated by spaces, e.g. #meeting #review",
+            Some(CurrentlyEditing::Date) => "Empty for today | today, yesterday, 3d or 2025-01-28",
+            Some(CurrentlyEditing::StartTime) if adding_past => {
+                "Empty to end the entry now | 14:30 or 14:30:00"
+            }
+            Some(CurrentlyEditing::Duration) if adding_past => "1h30m, 45m or 01:30:00",
+            Some(CurrentlyEditing::StartTime) => {
+                "Empty keeps the start | 14:30, 2025-01-28 14:30, +15m or -10m"
+            }
+            Some(CurrentlyEditing::Duration) => {
+                "Empty keeps the duration | 1h30m, 01:30:00, +15m or -10m"
+            }
+            _ => &format!(
+                "<{}> Save | <{}> Switch field | <{}> Back",
+                key(Action::Submit),
+                key(Action::NextField),
+                key(Action::Cancel)
+            ),
+        };
+        let help_paragraph = Paragraph::new(help_text).block(help_block);
+        frame.render_widget(help_paragraph, main_chunks[field_rows as usize + 1]);
     }
 }
@@ -1,283 +1,196 @@
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode};
+use clap::Parser;
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event,
+};
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
 use crossterm::{event, execute};
 use ratatui::Terminal;
-use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::backend::CrosstermBackend;
+use std::backtrace::Backtrace;
 use std::error::Error;
 use std::io;
-use std::io::{BufWriter, StderrLock};
+use std::io::{BufWriter, StderrLock, Write};
+use std::panic::{self, PanicHookInfo};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use time_rs::lib::app::{App, CurrentScreen, CurrentlyEditing};
+use time_rs::lib::app::{App, CurrentScreen};
+use time_rs::lib::cli::{self, Cli};
+use time_rs::lib::clock::SystemClock;
+use time_rs::lib::config::Config;
+use time_rs::lib::crash;
+use time_rs::lib::editor;
 use time_rs::lib::ui::ui;
 
-struct DeleteKeyPressState {
-    pressed: bool,
-    time_pressed: Option<Instant>,
-}
-
 fn main() -> Result<(), Box<dyn Error>> {
-    let (mut terminal, mut app) = initialize_app()?;
+    let cli = Cli::parse();
+    let mut config = Config::load(cli.config.as_deref())?;
+    if let Some(command) = cli.command {
+        let db = config.open_db()?;
+        return cli::run(command, &db, &config, &SystemClock, &mut io::stdout());
+    }
+    if cli.daily_target.is_some() {
+        config.daily_target = cli.daily_target;
+    }
+
+    if let Some(path) = crash::report_path()
+        && path.exists()
+    {
+        crash::offer_report(&path, &mut io::stdin().lock(), &mut io::stderr())?;
+    }
+
+    // Open the database before taking over the terminal, so errors that keep
+    // the app from starting are printed normally
+    let mut app = App::new(config)?;
+    if let Err(e) = app.load() {
+        app.report_error(e);
+    }
 
-    run_app(&mut terminal, &mut app).expect("TODO: panic message");
+    let app_state = Arc::new(Mutex::new(app.summary()));
+    install_panic_hook(app_state.clone());
+    let mut terminal = initialize_terminal()?;
+
+    let result = run_app(&mut terminal, &mut app, &app_state);
 
     // restore terminal
     restore_terminal(&mut terminal);
 
-    Ok(())
+    Ok(result?)
 }
 
-fn initialize_app() -> Result<
-    (
-        Terminal<CrosstermBackend<BufWriter<StderrLock<'static>>>>,
-        App,
-    ),
-    Box<dyn Error>,
-> {
-    enable_raw_mode()?;
+type AppTerminal = Terminal<CrosstermBackend<BufWriter<StderrLock<'static>>>>;
+
+fn initialize_terminal() -> io::Result<AppTerminal> {
     let stderr = io::stderr();
     let mut stderr = BufWriter::new(stderr.lock());
-
-    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    enter_tui(&mut stderr)?;
 
     let backend = CrosstermBackend::new(stderr);
-    let terminal = Terminal::new(backend)?;
+    Terminal::new(backend)
+}
 
-    let mut app = App::new().expect("Could not initialize app");
-    app.timers = app.db.get_timers_from_db().expect("Unable to load timers");
+fn enter_tui(out: &mut impl Write) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(
+        out,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )
+}
+
+/// Restore the terminal before anything is printed about a panic, and write a
+/// crash report with the last known state of the app
+fn install_panic_hook(app_state: Arc<Mutex<String>>) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stderr(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableBracketedPaste
+        );
+
+        if let Some(path) = crash::report_path() {
+            let app_state = app_state
+                .lock()
+                .map_or_else(|e| e.into_inner().clone(), |state| state.clone());
+            let report = crash::format_report(
+                &panic_message(info),
+                info.location().map(ToString::to_string).as_deref(),
+                &Backtrace::force_capture().to_string(),
+                &app_state,
+                chrono::Utc::now(),
+            );
+            if crash::write_report(&path, &report).is_ok() {
+                eprintln!("A crash report was written to {}", path.display());
+            }
+        }
+        default_hook(info);
+    }));
+}
 
-    Ok((terminal, app))
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
 }
 
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<BufWriter<StderrLock>>>) {
+fn restore_terminal(terminal: &mut AppTerminal) {
     disable_raw_mode().expect("Unable to disable raw mode");
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )
     .expect("Unable to leave alternate screen");
     terminal.show_cursor().expect("Unable to show cursor");
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
-    let tick_rate = Duration::from_millis(16);
-    let mut last_frame = Instant::now();
-    let mut time_accumulator = Duration::ZERO;
-    let mut delete_key_press_state = DeleteKeyPressState {
-        pressed: false,
-        time_pressed: None,
+/// Leave the TUI while the description of a timer is edited in $EDITOR
+fn edit_description(terminal: &mut AppTerminal, app: &mut App, id: usize) -> io::Result<()> {
+    let Some(description) = app
+        .timers
+        .iter()
+        .find(|timer| timer.id == id)
+        .map(|timer| timer.description.clone())
+    else {
+        return Ok(());
     };
 
+    restore_terminal(terminal);
+    let edited = editor::edit(&editor::command(), &description);
+    enter_tui(terminal.backend_mut())?;
+    terminal.clear()?;
+
+    if let Err(e) = edited.and_then(|description| app.set_description(id, description)) {
+        app.report_error(e);
+    }
+    Ok(())
+}
+
+fn run_app(terminal: &mut AppTerminal, app: &mut App, app_state: &Mutex<String>) -> io::Result<()> {
+    let tick_rate = Duration::from_millis(app.config.tick_rate_ms);
+    let mut last_throbber_tick = Instant::now();
+
     loop {
+        if let Ok(mut state) = app_state.lock() {
+            *state = app.summary();
+        }
         terminal.draw(|f| ui(f, app))?;
-        if let Some(last_timer) = app.timers.last_mut() {
-            let now = Instant::now();
-            let delta = now - last_frame;
-            last_frame = now;
-            time_accumulator += delta;
-            if time_accumulator >= Duration::from_secs(1) {
-                if last_timer.running {
-                    last_timer.tick();
-                    app.throbber.tick();
+        // Durations are derived from wall-clock timestamps, so the loop only
+        // has to keep the throbber spinning
+        if last_throbber_tick.elapsed() >= Duration::from_secs(1) {
+            if app.timers.iter().any(|timer| timer.running) {
+                app.throbber.tick();
+                if let CurrentScreen::Stats = app.current_screen
+                    && let Err(e) = app.refresh_stats()
+                {
+                    app.report_error(e);
                 }
-                time_accumulator -= Duration::from_secs(1);
-                app.db
-                    .update_timers_in_db(&app.timers)
-                    .expect("Unable to update timers");
             }
-        } else {
-            last_frame = Instant::now();
-            time_accumulator = Duration::ZERO;
+            last_throbber_tick = Instant::now();
         }
 
         if event::poll(tick_rate)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Release {
-                    continue;
-                }
-                match app.current_screen {
-                    CurrentScreen::Main => match key.code {
-                        KeyCode::Esc => {
-                            app.current_screen = CurrentScreen::Exit;
-                        }
-                        KeyCode::Char('c')
-                            if key.modifiers.contains(event::KeyModifiers::CONTROL) =>
-                        {
-                            app.current_screen = CurrentScreen::Exit;
-                        }
-                        KeyCode::Char('j') => {
-                            app.next_row();
-                        }
-                        KeyCode::Char('k') => {
-                            app.previous_row();
-                        }
-                        KeyCode::Char('d') if !app.timers.is_empty() => {
-                            // Check if 'd' was already pressed recently
-                            if delete_key_press_state.pressed {
-                                // If 'd' was pressed within the last 500ms, delete the timer
-                                if let Some(time_pressed) = delete_key_press_state.time_pressed {
-                                    if time_pressed.elapsed() < Duration::from_millis(500) {
-                                        if let Err(e) = app.delete_selected_timer() {
-                                            eprintln!("Failed to delete timer: {}", e);
-                                        }
-                                    }
-                                }
-                                // Reset the state
-                                delete_key_press_state.pressed = false;
-                                delete_key_press_state.time_pressed = None;
-                            } else {
-                                // First 'd' press
-                                delete_key_press_state.pressed = true;
-                                delete_key_press_state.time_pressed = Some(Instant::now());
-                            }
-                        }
-                        KeyCode::Char('i') if key.modifiers.contains(event::KeyModifiers::ALT) => {
-                            app.current_screen = CurrentScreen::Add;
-                            app.currently_editing = Some(CurrentlyEditing::Name);
-                        }
-                        KeyCode::Char('e') if !app.timers.is_empty() => {
-                            app.current_screen = CurrentScreen::Edit;
-                            app.currently_editing = Some(CurrentlyEditing::Name);
-                            if let Some(selected) = app.state.selected() {
-                                if let Some(timer_index) = app.get_timer_index_from_selection(selected) {
-                                    app.name_input = app.timers[timer_index].name.clone();
-                                    app.description_input = app.timers[timer_index].description.clone();
-                                }
-                            }
-                        }
-                        KeyCode::Char(' ') => {
-                            app.toggle_timer();
-                        }
-                        _ => {
-                            // Any other key press resets the delete key state
-                            delete_key_press_state.pressed = false;
-                            delete_key_press_state.time_pressed = None;
-                        }
-                    },
-                    CurrentScreen::Exit => match key.code {
-                        KeyCode::Char('q') | KeyCode::Char('n') => {
-                            app.current_screen = CurrentScreen::Main;
-                        }
-                        KeyCode::Char('c')
-                            if key.modifiers.contains(event::KeyModifiers::CONTROL) =>
-                        {
-                            return Ok(());
-                        }
-                        KeyCode::Char('y') => {
-                            return Ok(());
-                        }
-                        KeyCode::Tab => {
-                            app.toggle_exit_button();
-                        }
-                        KeyCode::Enter => {
-                            if app.exit_button_selected {
-                                return Ok(()); // Yes selected - exit
-                            } else {
-                                app.current_screen = CurrentScreen::Main; // No selected - go back
-                            }
-                        }
-                        _ => {}
-                    },
-                    CurrentScreen::Add if key.kind == event::KeyEventKind::Press => {
-                        match key.code {
-                            KeyCode::Enter => {
-                                if let Some(edit_mode) = &app.currently_editing {
-                                    match edit_mode {
-                                        CurrentlyEditing::Name => {
-                                            app.currently_editing =
-                                                Some(CurrentlyEditing::Description)
-                                        }
-                                        CurrentlyEditing::Description => {
-                                            app.add_timer();
-                                            app.current_screen = CurrentScreen::Main
-                                        }
-                                    }
-                                }
-                            }
-                            KeyCode::Backspace => {
-                                handle_backspace(app);
-                            }
-                            KeyCode::Esc => {
-                                handle_escape(app);
-                            }
-                            KeyCode::Tab => {
-                                app.toggle_editing();
-                            }
-                            KeyCode::Char(c) => {
-                                handle_input(app, c);
-                            }
-                            _ => (),
-                        }
-                    }
-                    CurrentScreen::Edit if key.kind == event::KeyEventKind::Press => {
-                        match key.code {
-                            KeyCode::Enter => {
-                                if let Some(edit_mode) = &app.currently_editing {
-                                    match edit_mode {
-                                        CurrentlyEditing::Name => {
-                                            app.currently_editing =
-                                                Some(CurrentlyEditing::Description)
-                                        }
-                                        CurrentlyEditing::Description => {
-                                            app.edit_timer();
-                                            app.current_screen = CurrentScreen::Main
-                                        }
-                                    }
-                                }
-                            }
-                            KeyCode::Backspace => {
-                                handle_backspace(app);
-                            }
-                            KeyCode::Esc => {
-                                handle_escape(app);
-                            }
-                            KeyCode::Tab => {
-                                app.toggle_editing();
-                            }
-                            KeyCode::Char(c) => {
-                                handle_input(app, c);
-                            }
-                            _ => (),
-                        }
+            match event::read()? {
+                Event::Key(key) => {
+                    app.handle_key(&key);
+                    if let Some(id) = app.pending_editor.take() {
+                        edit_description(terminal, app, id)?;
                     }
-                    CurrentScreen::Add => {}
-                    CurrentScreen::Edit => {}
                 }
+                Event::Paste(text) => app.paste(&text),
+                _ => {}
             }
-        } else {
-            continue;
-        }
-    }
-}
-
-fn handle_input(app: &mut App, c: char) {
-    if let Some(edit_mode) = &app.currently_editing {
-        match edit_mode {
-            CurrentlyEditing::Name => {
-                app.name_input.push(c);
-            }
-            CurrentlyEditing::Description => {
-                app.description_input.push(c);
-            }
-        }
-    }
-}
-
-fn handle_escape(app: &mut App) {
-    app.current_screen = CurrentScreen::Main;
-    app.currently_editing = None;
-}
-
-fn handle_backspace(app: &mut App) {
-    if let Some(edit_mode) = &app.currently_editing {
-        match edit_mode {
-            CurrentlyEditing::Name => {
-                app.name_input.pop();
-            }
-            CurrentlyEditing::Description => {
-                app.description_input.pop();
+            if app.should_quit {
+                return Ok(());
             }
         }
     }
@@ -0,0 +1,13 @@
+-- Schema and data as written by time-rs 1.0.5, before schema versioning
+CREATE TABLE IF NOT EXISTS timers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    start_time DATETIME NOT NULL,
+    duration INTEGER NOT NULL,
+    running BOOLEAN NOT NULL
+);
+
+INSERT INTO timers (name, description, start_time, duration, running) VALUES
+    ('Work Session', 'Deep focus work', '2025-01-27T08:00:00.123456789+00:00', 5025, 0),
+    ('Break Timer', 'Short break', '2025-01-28T10:15:00+00:00', 300, 1);
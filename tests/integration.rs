@@ -1,8 +1,30 @@
-use ratatui::widgets::TableState;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use clap::Parser;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Position, Rect};
+use ratatui::style::Color;
+use ratatui::widgets::StatefulWidget;
+use std::rc::Rc;
 use tempfile::TempDir;
-use time_rs::lib::app::{App, CurrentScreen, Timer};
+use time_rs::lib::app::{
+    App, CurrentScreen, CurrentlyEditing, Project, Status, Timer, format_duration,
+};
+use time_rs::lib::cli::{self, Cli, Command};
+use time_rs::lib::clock::{Clock, FakeClock, SystemClock};
+use time_rs::lib::config::{Config, DeleteConfirmation, Theme};
+use time_rs::lib::crash;
 use time_rs::lib::db::Db;
-use time_rs::lib::throbber::Throbber;
+use time_rs::lib::editor;
+use time_rs::lib::error::Error;
+use time_rs::lib::export::{DateRange, ExportFormat, TimerRecord};
+use time_rs::lib::import;
+use time_rs::lib::input::{InputField, TextInput};
+use time_rs::lib::keymap::{Action, KeyBinding, KeyContext, Keymap};
+use time_rs::lib::migrations;
+use time_rs::lib::parse;
+use time_rs::lib::search::Search;
+use time_rs::lib::stats::{Stats, StatsRange};
 
 pub struct DBTestFixture {
     pub db: Db,
@@ -12,13 +34,20 @@ pub struct DBTestFixture {
 impl DBTestFixture {
     pub fn new() -> Self {
         let temp_dir = TempDir::new().unwrap();
-        let db = Db::new(temp_dir.path().join("test.db").to_str().unwrap());
+        let db = Db::open(temp_dir.path().join("test.db").to_str().unwrap()).unwrap();
         Self { db, temp_dir }
     }
 }
 
+impl Default for DBTestFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct AppTestFixture {
     pub app: App,
+    pub clock: Rc<FakeClock>,
     pub temp_dir: TempDir,
 }
 
@@ -26,31 +55,31 @@ impl AppTestFixture {
     pub fn new() -> Self {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let db = Db::new(db_path.to_str().unwrap());
+        let db = Db::open(db_path.to_str().unwrap()).unwrap();
 
-        let app = App {
-            state: TableState::default().with_selected(1),
-            timers: Vec::new(),
-            current_screen: CurrentScreen::Main,
-            name_input: String::new(),
-            description_input: String::new(),
-            currently_editing: None,
-            selectable_rows: Vec::new(),
-            exit_button_selected: false,
-            db,
-            throbber: Throbber::new(),
-        };
+        let clock = Rc::new(FakeClock::new(Utc::now()));
+        let app = App::with_clock(db, clock.clone());
 
-        Self { app, temp_dir }
+        Self {
+            app,
+            clock,
+            temp_dir,
+        }
+    }
+}
+
+impl Default for AppTestFixture {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[test]
 fn test_add_timer_to_app() {
     let mut fixture = AppTestFixture::new();
-    fixture.app.name_input = "test".to_string();
-    fixture.app.description_input = "test".to_string();
-    fixture.app.add_timer();
+    fixture.app.name_input.set("test");
+    fixture.app.description_input.set("test");
+    fixture.app.add_timer().unwrap();
     assert_eq!(fixture.app.timers.len(), 1);
 }
 
@@ -65,7 +94,7 @@ fn test_init_db() {
 #[test]
 fn test_add_timer_to_db() {
     let fixture = DBTestFixture::new();
-    let mut timer = Timer::new("test".to_string(), "test".to_string());
+    let mut timer = Timer::new("test".to_string(), "test".to_string(), Utc::now());
     fixture.db.add_timer_to_db(&mut timer).unwrap();
     let timers = fixture.db.get_timers_from_db().unwrap();
     let count = timers.len();
@@ -75,8 +104,8 @@ fn test_add_timer_to_db() {
 #[test]
 fn test_edit_timer() {
     let fixture = DBTestFixture::new();
-    let mut timer = Timer::new("test".to_string(), "test".to_string());
-    let mut timer2 = Timer::new("test2".to_string(), "test2".to_string());
+    let mut timer = Timer::new("test".to_string(), "test".to_string(), Utc::now());
+    let mut timer2 = Timer::new("test2".to_string(), "test2".to_string(), Utc::now());
 
     fixture.db.add_timer_to_db(&mut timer).unwrap();
     fixture.db.add_timer_to_db(&mut timer2).unwrap();
@@ -85,7 +114,7 @@ fn test_edit_timer() {
     timer.description = "test edited".to_string();
     fixture
         .db
-        .edit_timer(&timer, "test edited", "test edited")
+        .edit_timer(&mut timer, "test edited", "test edited")
         .unwrap();
 
     let timers = fixture.db.get_timers_from_db().unwrap();
@@ -98,3 +127,2031 @@ fn test_edit_timer() {
     assert_eq!(timer2.name.as_str(), "test2");
     assert_eq!(timer2.description.as_str(), "test2");
 }
+
+#[test]
+fn test_sessions_are_persisted_per_start_stop_cycle() {
+    let fixture = DBTestFixture::new();
+    let mut timer = Timer::new("test".to_string(), "test".to_string(), Utc::now());
+    fixture.db.add_timer_to_db(&mut timer).unwrap();
+
+    timer.stop(Utc::now());
+    timer.start(Utc::now());
+    timer.stop(Utc::now());
+    let mut timers = vec![timer];
+    fixture.db.update_timers_in_db(&mut timers).unwrap();
+
+    let timers = fixture.db.get_timers_from_db().unwrap();
+    let timer = timers.first().unwrap();
+    assert_eq!(timer.sessions.len(), 2);
+    assert!(timer.sessions.iter().all(|s| s.ended_at.is_some()));
+    assert!(!timer.running);
+}
+
+#[test]
+fn test_running_timer_counts_from_persisted_start() {
+    let fixture = DBTestFixture::new();
+    let now = Utc::now();
+    let mut timer = Timer::new("test".to_string(), "test".to_string(), now);
+    timer.sessions[0].started_at = now - Duration::hours(1);
+    fixture.db.add_timer_to_db(&mut timer).unwrap();
+
+    let timers = fixture.db.get_timers_from_db().unwrap();
+    let timer = timers.first().unwrap();
+    assert!(timer.running);
+    assert!(timer.sessions[0].ended_at.is_none());
+    assert_eq!(timer.duration(now), Duration::hours(1));
+    assert_eq!(
+        timer.formatted_duration(now + Duration::minutes(30)),
+        "01:30:00"
+    );
+}
+
+fn create_v0_db(path: &std::path::Path) {
+    let conn = rusqlite::Connection::open(path).unwrap();
+    conn.execute_batch(include_str!("fixtures/timers_v0.sql"))
+        .unwrap();
+}
+
+#[test]
+fn test_migrate_v0_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("timers.db");
+    create_v0_db(&path);
+
+    let db = Db::open(path.to_str().unwrap()).unwrap();
+    let timers = db.get_timers_from_db().unwrap();
+    assert_eq!(timers.len(), 2);
+
+    let work = &timers[0];
+    assert_eq!(work.name, "Work Session");
+    assert_eq!(
+        work.start_time,
+        "2025-01-27T08:00:00.123456789Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+    );
+    assert_eq!(work.sessions.len(), 1);
+    assert_eq!(work.duration(Utc::now()), Duration::seconds(5025));
+    assert!(!work.running);
+
+    let running = &timers[1];
+    assert!(running.running);
+    assert_eq!(running.sessions.len(), 2);
+    assert!(running.sessions[1].ended_at.is_none());
+    assert!(running.duration(Utc::now()) >= Duration::seconds(300));
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    assert_eq!(
+        migrations::current_version(&conn).unwrap(),
+        migrations::latest_version()
+    );
+}
+
+#[test]
+fn test_migrations_are_idempotent() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("timers.db");
+    create_v0_db(&path);
+
+    drop(Db::open(path.to_str().unwrap()).unwrap());
+    let db = Db::open(path.to_str().unwrap()).unwrap();
+    let timers = db.get_timers_from_db().unwrap();
+    assert_eq!(timers[0].sessions.len(), 1);
+    assert_eq!(timers[1].sessions.len(), 2);
+}
+
+#[test]
+fn test_refuse_newer_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("timers.db");
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.pragma_update(None, "user_version", migrations::latest_version() + 1)
+        .unwrap();
+    drop(conn);
+
+    assert!(Db::open(path.to_str().unwrap()).is_err());
+}
+
+fn run_cli(db: &Db, command: Command) -> String {
+    run_cli_with_config(db, &Config::default(), command)
+}
+
+fn run_cli_with_config(db: &Db, config: &Config, command: Command) -> String {
+    let mut out = Vec::new();
+    cli::run(command, db, config, &SystemClock, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_cli_start_stop_status() {
+    let fixture = DBTestFixture::new();
+    let start = |name: &str| Command::Start {
+        name: name.to_string(),
+        description: "desc".to_string(),
+        duration: None,
+        date: None,
+        at: None,
+    };
+
+    assert_eq!(
+        run_cli(&fixture.db, Command::Status),
+        "No timer is running\n"
+    );
+    run_cli(&fixture.db, start("first"));
+    let output = run_cli(&fixture.db, start("second"));
+    assert!(output.contains("Stopped 'first'"));
+    assert!(output.contains("Started 'second'"));
+
+    let status = run_cli(&fixture.db, Command::Status);
+    assert!(status.starts_with("Running 'second'"));
+
+    run_cli(&fixture.db, Command::Stop);
+    let timers = fixture.db.get_timers_from_db().unwrap();
+    assert_eq!(timers.len(), 2);
+    assert!(timers.iter().all(|timer| !timer.running));
+
+    // The config decides whether starting a timer stops the others
+    let config = Config {
+        allow_multiple_running: true,
+        ..Config::default()
+    };
+    run_cli_with_config(&fixture.db, &config, start("third"));
+    let output = run_cli_with_config(&fixture.db, &config, start("fourth"));
+    assert!(!output.contains("Stopped"));
+    let running = fixture.db.get_timers_from_db().unwrap();
+    assert_eq!(running.iter().filter(|timer| timer.running).count(), 2);
+}
+
+#[test]
+fn test_cli_list_since() {
+    let fixture = DBTestFixture::new();
+    let mut old = Timer::new("old".to_string(), String::new(), Utc::now());
+    old.start_time = Utc::now() - Duration::days(3);
+    fixture.db.add_timer_to_db(&mut old).unwrap();
+    let mut new = Timer::new("new".to_string(), String::new(), Utc::now());
+    fixture.db.add_timer_to_db(&mut new).unwrap();
+
+    let all = run_cli(&fixture.db, Command::List { since: None });
+    assert!(all.contains("old") && all.contains("new"));
+
+    let recent = run_cli(
+        &fixture.db,
+        Command::List {
+            since: Some("yesterday".to_string()),
+        },
+    );
+    assert!(!recent.contains("old"));
+    assert!(recent.contains("new"));
+
+    let config = Config {
+        date_format: "%Y/%m/%d".to_string(),
+        ..Config::default()
+    };
+    let listed = run_cli_with_config(&fixture.db, &config, Command::List { since: None });
+    assert!(listed.contains(&Local::now().format("%Y/%m/%d").to_string()));
+}
+
+#[test]
+fn test_parse_date() {
+    let today = NaiveDate::from_ymd_opt(2025, 1, 28).unwrap();
+    assert_eq!(parse::parse_date("today", today), Ok(today));
+    assert_eq!(
+        parse::parse_date("yesterday", today),
+        Ok(NaiveDate::from_ymd_opt(2025, 1, 27).unwrap())
+    );
+    assert_eq!(
+        parse::parse_date("7d", today),
+        Ok(NaiveDate::from_ymd_opt(2025, 1, 21).unwrap())
+    );
+    assert_eq!(
+        parse::parse_date("2024-12-31", today),
+        Ok(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap())
+    );
+    assert!(parse::parse_date("someday", today).is_err());
+    assert!(parse::parse_date("999999999d", today).is_err());
+    assert!(parse::parse_date("9223372036854775807d", today).is_err());
+}
+
+/// A writer whose disk is always full
+struct FullDisk;
+
+impl std::io::Write for FullDisk {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_export_write_errors() {
+    let fixture = DBTestFixture::new();
+    let mut timer = Timer::new("timer".to_string(), String::new(), Utc::now());
+    fixture.db.add_timer_to_db(&mut timer).unwrap();
+    let range = DateRange::default();
+
+    let csv = fixture
+        .db
+        .export_timers(ExportFormat::Csv, &range, Utc::now(), &mut FullDisk);
+    // The CSV writer buffers, so the failure shows up when it is flushed
+    assert!(matches!(csv, Err(Error::Io(_))));
+    let json = fixture
+        .db
+        .export_timers(ExportFormat::Json, &range, Utc::now(), &mut FullDisk);
+    assert!(matches!(json, Err(Error::Json(_))));
+    assert!(json.unwrap_err().to_string().contains("disk full"));
+}
+
+#[test]
+fn test_get_timer_loads_only_its_own_rows() {
+    let fixture = DBTestFixture::new();
+    let db = &fixture.db;
+    let mut first = Timer::new("first".to_string(), String::new(), Utc::now());
+    first.tags = parse::parse_tags("#a");
+    db.add_timer_to_db(&mut first).unwrap();
+    let mut second = Timer::new("second".to_string(), String::new(), Utc::now());
+    second.tags = parse::parse_tags("#b #c");
+    second.stop(Utc::now());
+    second.start(Utc::now());
+    db.add_timer_to_db(&mut second).unwrap();
+
+    let loaded = db.get_timer(second.id).unwrap().unwrap();
+    assert_eq!(loaded.sessions, second.sessions);
+    assert_eq!(loaded.tags, vec!["b", "c"]);
+    let loaded = db.get_timer(first.id).unwrap().unwrap();
+    assert_eq!(loaded.sessions, first.sessions);
+    assert_eq!(loaded.tags, vec!["a"]);
+    assert_eq!(db.get_timer(second.id + 1).unwrap(), None);
+}
+
+#[test]
+fn test_export_csv_and_json() {
+    let fixture = DBTestFixture::new();
+    let mut old = Timer::new("old".to_string(), String::new(), Utc::now());
+    old.start_time = Utc::now() - Duration::days(3);
+    fixture.db.add_timer_to_db(&mut old).unwrap();
+    let mut timer = Timer::new(
+        "Invoice, client".to_string(),
+        "with \"quotes\"".to_string(),
+        Utc::now(),
+    );
+    timer.sessions[0].started_at = Utc::now() - Duration::seconds(90);
+    timer.stop(Utc::now());
+    fixture.db.add_timer_to_db(&mut timer).unwrap();
+
+    let range = DateRange {
+        since: Some(Local::now().date_naive()),
+        until: None,
+    };
+
+    let mut csv = Vec::new();
+    let count = fixture
+        .db
+        .export_timers(ExportFormat::Csv, &range, Utc::now(), &mut csv)
+        .unwrap();
+    assert_eq!(count, 1);
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,name,description,start_time,duration_seconds,running")
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("2,\"Invoice, client\",\"with \"\"quotes\"\"\","));
+    assert!(row.ends_with(",90,false"));
+    assert_eq!(lines.next(), None);
+
+    let mut json = Vec::new();
+    fixture
+        .db
+        .export_timers(
+            ExportFormat::Json,
+            &DateRange::default(),
+            Utc::now(),
+            &mut json,
+        )
+        .unwrap();
+    let records: Vec<TimerRecord> = serde_json::from_slice(&json).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].name, "Invoice, client");
+    assert_eq!(records[1].duration_seconds, 90);
+    assert_eq!(records[1].start_time, timer.start_time);
+    assert!(!records[1].running);
+}
+
+#[test]
+fn test_import_csv_reports_errors_and_duplicates() {
+    let fixture = DBTestFixture::new();
+    let input = "\
+id,name,description,start_time,duration_seconds,running
+1,Meeting,Weekly sync,2025-01-27T14:00:00+00:00,7200,false
+2,Review,PR review,not a date,60,false
+3,Meeting,Weekly sync,2025-01-27T14:00:00+00:00,7200,false
+4,Broken,row
+5,Planning,Sprint,2025-01-28T09:00:00+00:00,-5,false
+";
+
+    let report = import::import_timers(&fixture.db, input, ExportFormat::Csv, true).unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.duplicates, 1);
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![3, 5, 6]);
+    assert!(report.errors[0].message.starts_with("invalid record"));
+    assert!(fixture.db.get_timers_from_db().unwrap().is_empty());
+
+    let report = import::import_timers(&fixture.db, input, ExportFormat::Csv, false).unwrap();
+    assert_eq!(report.imported, 1);
+    let timers = fixture.db.get_timers_from_db().unwrap();
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers[0].duration(Utc::now()), Duration::hours(2));
+    assert!(!timers[0].running);
+
+    // Importing the same file again only finds duplicates
+    let report = import::import_timers(&fixture.db, input, ExportFormat::Csv, false).unwrap();
+    assert_eq!(report.imported, 0);
+    assert_eq!(report.duplicates, 2);
+}
+
+#[test]
+fn test_import_json_export_round_trip() {
+    let source = DBTestFixture::new();
+    let mut timer = Timer::new("Work".to_string(), "Focus".to_string(), Utc::now());
+    timer.sessions[0].started_at = Utc::now() - Duration::minutes(30);
+    timer.stop(Utc::now());
+    source.db.add_timer_to_db(&mut timer).unwrap();
+    let mut json = Vec::new();
+    source
+        .db
+        .export_timers(
+            ExportFormat::Json,
+            &DateRange::default(),
+            Utc::now(),
+            &mut json,
+        )
+        .unwrap();
+
+    let target = DBTestFixture::new();
+    let input = String::from_utf8(json).unwrap();
+    let report = import::import_timers(&target.db, &input, ExportFormat::Json, false).unwrap();
+    assert_eq!(report.imported, 1);
+    assert!(report.errors.is_empty());
+    let timers = target.db.get_timers_from_db().unwrap();
+    assert_eq!(timers[0].name, "Work");
+    assert_eq!(timers[0].start_time, timer.start_time);
+    assert_eq!(timers[0].duration(Utc::now()).num_seconds(), 30 * 60);
+
+    let broken = "[\n  {\"name\": \"x\"},\n  {\"id\": 1}\n]";
+    let report = import::import_timers(&target.db, broken, ExportFormat::Json, true).unwrap();
+    let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![2, 3]);
+}
+
+#[test]
+fn test_import_rejects_huge_durations() {
+    let input = r#"[
+  {"id": 1, "name": "Ok", "description": "", "start_time": "2025-01-27T14:00:00+00:00", "duration_seconds": 60, "running": false},
+  {"id": 2, "name": "Max", "description": "", "start_time": "2025-01-27T14:00:00+00:00", "duration_seconds": 9223372036854775807, "running": false},
+  {"id": 3, "name": "Far", "description": "", "start_time": "2025-01-27T14:00:00+00:00", "duration_seconds": 9223372036854775, "running": false}
+]"#;
+
+    for dry_run in [true, false] {
+        let target = DBTestFixture::new();
+        let report = import::import_timers(&target.db, input, ExportFormat::Json, dry_run).unwrap();
+        assert_eq!(report.imported, 1);
+        let lines: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+        assert!(report.errors[0].message.contains("too long"));
+        let stored = target.db.get_timers_from_db().unwrap().len();
+        assert_eq!(stored, if dry_run { 0 } else { 1 });
+    }
+}
+
+/// Add timers named after `names` and lay them out as a single date group
+fn add_timers(app: &mut App, names: &[&str]) {
+    for name in names {
+        app.name_input.set(*name);
+        app.add_timer().unwrap();
+    }
+    app.selectable_rows = std::iter::once(false)
+        .chain(app.timers.iter().map(|_| true))
+        .collect();
+}
+
+fn running_names(app: &App) -> Vec<&str> {
+    app.timers
+        .iter()
+        .filter(|timer| timer.running)
+        .map(|timer| timer.name.as_str())
+        .collect()
+}
+
+#[test]
+fn test_toggle_selected_timer() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    add_timers(app, &["first", "second", "third"]);
+    assert_eq!(running_names(app), vec!["third"]);
+
+    app.state.select(Some(1));
+    app.toggle_timer().unwrap();
+    assert_eq!(running_names(app), vec!["first"]);
+
+    app.toggle_timer().unwrap();
+    assert!(running_names(app).is_empty());
+
+    let timers = app.db.get_timers_from_db().unwrap();
+    assert!(timers.iter().all(|timer| !timer.running));
+    assert_eq!(timers[0].sessions.len(), 2);
+}
+
+#[test]
+fn test_toggle_allows_multiple_running_timers() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    app.config.allow_multiple_running = true;
+    add_timers(app, &["first", "second"]);
+    assert_eq!(running_names(app), vec!["first", "second"]);
+
+    app.state.select(Some(2));
+    app.toggle_timer().unwrap();
+    app.toggle_timer().unwrap();
+    assert_eq!(running_names(app), vec!["first", "second"]);
+}
+
+#[test]
+fn test_continue_past_timer_today() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    let mut old = Timer::stopped(
+        "Report".to_string(),
+        "Quarterly".to_string(),
+        Utc::now() - Duration::days(2),
+        Duration::hours(1),
+    );
+    app.db.add_timer_to_db(&mut old).unwrap();
+    app.timers = app.db.get_timers_from_db().unwrap();
+    app.selectable_rows = vec![false, true];
+    app.state.select(Some(1));
+
+    app.continue_timer().unwrap();
+
+    assert_eq!(app.timers.len(), 2);
+    let continued = &app.timers[1];
+    assert_eq!(continued.name, "Report");
+    assert_eq!(continued.description, "Quarterly");
+    assert!(continued.running);
+    assert_ne!(continued.formatted_date(), app.timers[0].formatted_date());
+    // date row, old timer, today's date row, continued timer
+    assert_eq!(app.state.selected(), Some(3));
+    assert_eq!(app.db.get_timers_from_db().unwrap().len(), 2);
+}
+
+#[test]
+fn test_parse_duration_change() {
+    use parse::DurationChange::{Add, Set};
+    assert_eq!(
+        parse::parse_duration_change("1h30m"),
+        Ok(Set(Duration::minutes(90)))
+    );
+    assert_eq!(
+        parse::parse_duration_change("01:30:15"),
+        Ok(Set(Duration::seconds(5415)))
+    );
+    assert_eq!(
+        parse::parse_duration_change("45"),
+        Ok(Set(Duration::minutes(45)))
+    );
+    assert_eq!(
+        parse::parse_duration_change("+15m"),
+        Ok(Add(Duration::minutes(15)))
+    );
+    assert_eq!(
+        parse::parse_duration_change("-10m"),
+        Ok(Add(Duration::minutes(-10)))
+    );
+    assert!(parse::parse_duration_change("1x").is_err());
+    assert!(parse::parse_duration_change("-").is_err());
+    assert!(parse::parse_duration_change("1:75").is_err());
+    // Too large to represent instead of a panic
+    assert!(parse::parse_duration_change("9999999999999h").is_err());
+    assert!(parse::parse_duration_change("+999999999999999m").is_err());
+    assert!(parse::parse_duration_change("99999999999999999999").is_err());
+    assert!(parse::parse_duration_change("2000000000000h2000000000000h").is_err());
+    assert_eq!(
+        Add(Duration::MAX).apply(Duration::hours(1)),
+        None,
+        "an adjustment that overflows is rejected"
+    );
+}
+
+#[test]
+fn test_edit_duration_and_start_time() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    let start = Utc::now() - Duration::days(1);
+    let mut timer = Timer::stopped("Work".to_string(), String::new(), start, Duration::hours(3));
+    timer.start(start + Duration::hours(4));
+    timer.stop(start + Duration::hours(4));
+    app.db.add_timer_to_db(&mut timer).unwrap();
+    app.timers = app.db.get_timers_from_db().unwrap();
+    app.selectable_rows = vec![false, true];
+    app.state.select(Some(1));
+
+    app.begin_edit();
+    app.duration_input.set("-3h10m");
+    assert!(app.edit_timer().is_err());
+    app.duration_input.set("+15m");
+    app.start_time_input.set("-1h");
+    app.edit_timer().unwrap();
+
+    let timers = app.db.get_timers_from_db().unwrap();
+    let edited = &timers[0];
+    assert_eq!(edited.start_time, start - Duration::hours(1));
+    assert_eq!(edited.sessions[0].started_at, start - Duration::hours(1));
+    assert!(
+        (edited.duration(Utc::now()) - Duration::minutes(195))
+            .num_seconds()
+            .abs()
+            <= 1
+    );
+
+    // Shortening beyond the last session eats into the earlier ones
+    app.begin_edit();
+    app.duration_input.set("1h");
+    app.edit_timer().unwrap();
+    let timers = app.db.get_timers_from_db().unwrap();
+    assert_eq!(timers[0].duration(Utc::now()), Duration::hours(1));
+    assert_eq!(timers[0].sessions[1].duration(Utc::now()), Duration::zero());
+
+    app.begin_edit();
+    app.start_time_input.set("tomorrow");
+    assert!(app.edit_timer().is_err());
+}
+
+#[test]
+fn test_edit_start_time_moves_timer_to_its_day() {
+    let mut fixture = AppTestFixture::new();
+    let today = NaiveDate::from_ymd_opt(2025, 1, 20).unwrap();
+    let noon = today.and_hms_opt(12, 0, 0).unwrap();
+    fixture
+        .clock
+        .set(Local.from_local_datetime(&noon).unwrap().to_utc());
+    let app = &mut fixture.app;
+    let now = app.clock.now();
+    for (name, minutes_ago) in [("A", 60), ("B", 30)] {
+        let mut timer = Timer::stopped(
+            name.to_string(),
+            String::new(),
+            now - Duration::minutes(minutes_ago),
+            Duration::minutes(10),
+        );
+        app.db.add_timer_to_db(&mut timer).unwrap();
+    }
+    app.timers = app.db.get_timers_from_db().unwrap();
+    app.update_selectable_rows();
+    app.state.select(Some(2));
+
+    app.begin_edit();
+    app.start_time_input.set("2025-01-18 09:00");
+    app.edit_timer().unwrap();
+
+    let names: Vec<&str> = app.timers.iter().map(|timer| timer.name.as_str()).collect();
+    assert_eq!(names, vec!["B", "A"]);
+    assert_eq!(app.selectable_rows, vec![false, true, false, true]);
+    assert_eq!(app.selected_timer().unwrap().name, "B");
+    assert_eq!(app.state.selected(), Some(1));
+}
+
+#[test]
+fn test_edit_duration_cannot_end_in_the_future() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    let now = app.clock.now();
+    let mut timer = Timer::stopped(
+        "Work".to_string(),
+        String::new(),
+        now - Duration::minutes(10),
+        Duration::minutes(5),
+    );
+    app.db.add_timer_to_db(&mut timer).unwrap();
+    app.timers = app.db.get_timers_from_db().unwrap();
+    app.selectable_rows = vec![false, true];
+    app.state.select(Some(1));
+
+    app.begin_edit();
+    app.duration_input.set("3h");
+    assert!(app.edit_timer().is_err());
+    app.duration_input.set("+9999999999h");
+    assert!(app.edit_timer().is_err());
+    // Within the time since the start, but the session would still run past now
+    app.duration_input.set("+2m");
+    app.start_time_input.set("+5m");
+    assert!(app.edit_timer().is_err());
+    assert_eq!(app.timers[0].duration(now), Duration::minutes(5));
+    assert_eq!(
+        app.db.get_timers_from_db().unwrap()[0].sessions,
+        timer.sessions
+    );
+
+    app.start_time_input.clear();
+    app.duration_input.set("10m");
+    app.edit_timer().unwrap();
+    assert_eq!(
+        app.db.get_timers_from_db().unwrap()[0].duration(now),
+        Duration::minutes(10)
+    );
+}
+
+#[test]
+fn test_edit_duration_keeps_sessions_apart() {
+    let mut fixture = AppTestFixture::new();
+    let at = |hour, minute| {
+        NaiveDate::from_ymd_opt(2025, 1, 20)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+            .and_utc()
+    };
+    fixture.clock.set(at(20, 10));
+    let app = &mut fixture.app;
+    let now = app.clock.now();
+    let mut timer = Timer::stopped(
+        "Work".to_string(),
+        String::new(),
+        at(17, 40),
+        Duration::minutes(30),
+    );
+    timer.start(at(19, 10));
+    timer.stop(at(19, 40));
+    timer.start(at(20, 0));
+    app.db.add_timer_to_db(&mut timer).unwrap();
+    app.timers = app.db.get_timers_from_db().unwrap();
+    app.update_selectable_rows();
+    app.state.select(Some(1));
+    assert_eq!(app.timers[0].duration(now), Duration::minutes(70));
+
+    // The running session can only grow back to the end of the previous one
+    app.begin_edit();
+    app.duration_input.set("2h30m");
+    assert!(app.edit_timer().is_err());
+    assert_eq!(
+        app.db.get_timers_from_db().unwrap()[0].sessions,
+        timer.sessions
+    );
+    app.duration_input.set("+20m");
+    app.edit_timer().unwrap();
+    let sessions = &app.db.get_timers_from_db().unwrap()[0].sessions;
+    assert_eq!(sessions[2].started_at, at(19, 40));
+    assert!(!app.timers[0].sessions_overlap());
+}
+
+#[test]
+fn test_edit_duration_of_timer_without_sessions_is_saved() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    let now = app.clock.now();
+    let mut timer = Timer {
+        sessions: Vec::new(),
+        ..Timer::stopped(
+            "Legacy".to_string(),
+            String::new(),
+            now - Duration::hours(2),
+            Duration::zero(),
+        )
+    };
+    app.db.add_timer_to_db(&mut timer).unwrap();
+    app.timers = app.db.get_timers_from_db().unwrap();
+    app.selectable_rows = vec![false, true];
+    app.state.select(Some(1));
+
+    app.begin_edit();
+    app.duration_input.set("1h");
+    app.edit_timer().unwrap();
+
+    assert_ne!(app.timers[0].sessions[0].id, 0);
+    let stored = app.db.get_timers_from_db().unwrap();
+    assert_eq!(stored[0].sessions, app.timers[0].sessions);
+    assert_eq!(stored[0].duration(now), Duration::hours(1));
+}
+
+#[test]
+fn test_add_past_entry_keeps_running_timer() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    add_timers(app, &["running"]);
+
+    app.begin_add_past();
+    app.name_input.set("Meeting");
+    app.date_input.set("yesterday");
+    app.start_time_input.set("14:00");
+    app.duration_input.set("2h");
+    app.add_past_timer().unwrap();
+
+    assert_eq!(running_names(app), vec!["running"]);
+    let meeting = &app.timers[0];
+    assert_eq!(meeting.name, "Meeting");
+    assert!(!meeting.running);
+    assert_eq!(meeting.duration(Utc::now()), Duration::hours(2));
+    // Dates and times are typed in local time
+    let yesterday = Local::now().date_naive() - Duration::days(1);
+    let expected = Local
+        .from_local_datetime(&yesterday.and_hms_opt(14, 0, 0).unwrap())
+        .unwrap();
+    assert_eq!(meeting.start_time, expected.to_utc());
+    assert_eq!(app.state.selected(), Some(1));
+
+    let timers = app.db.get_timers_from_db().unwrap();
+    assert_eq!(timers[0].name, "Meeting");
+    assert!(timers[1].running);
+}
+
+#[test]
+fn test_dialogs_open_empty() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+
+    // A past entry with tags leaves nothing behind for the next timer
+    app.apply(Action::AddPastEntry);
+    app.name_input.set("Meeting");
+    app.tags_input.set("#review");
+    app.duration_input.set("1h");
+    app.add_past_timer().unwrap();
+    app.apply(Action::AddTimer);
+    app.name_input.set("Write");
+    app.add_timer().unwrap();
+    assert!(app.timers[1].tags.is_empty());
+
+    // Neither does a cancelled edit
+    app.selectable_rows = vec![false, true, true];
+    app.state.select(Some(1));
+    app.apply(Action::EditTimer);
+    app.start_time_input.set("-5m");
+    app.apply(Action::Cancel);
+    app.apply(Action::AddTimer);
+    assert!(matches!(app.current_screen, CurrentScreen::Add));
+    for input in [
+        &app.name_input,
+        &app.description_input,
+        &app.project_input,
+        &app.tags_input,
+        &app.date_input,
+        &app.start_time_input,
+        &app.duration_input,
+    ] {
+        assert_eq!(input.value(), "");
+    }
+}
+
+#[test]
+fn test_past_entry_validation() {
+    let local = |date: NaiveDate, hour, minute| {
+        Local
+            .from_local_datetime(&date.and_hms_opt(hour, minute, 0).unwrap())
+            .unwrap()
+            .to_utc()
+    };
+    let now = local(NaiveDate::from_ymd_opt(2025, 1, 28).unwrap(), 12, 0);
+    assert!(parse::parse_past_entry(None, None, "0m", now).is_err());
+    assert!(parse::parse_past_entry(Some("yesterday"), None, "1h", now).is_err());
+    assert!(parse::parse_past_entry(None, Some("11:00"), "2h", now).is_err());
+    assert_eq!(
+        parse::parse_past_entry(None, None, "30m", now),
+        Ok((now - Duration::minutes(30), Duration::minutes(30)))
+    );
+    assert_eq!(
+        parse::parse_past_entry(Some("2025-01-20"), Some("09:15"), "1h", now),
+        Ok((
+            local(NaiveDate::from_ymd_opt(2025, 1, 20).unwrap(), 9, 15),
+            Duration::hours(1)
+        ))
+    );
+    // Out of the representable range instead of a panic
+    assert!(parse::parse_past_entry(None, None, "4000000000:00", now).is_err());
+    assert!(parse::parse_past_entry(None, Some("-3000000000h"), "1h", now).is_err());
+    assert!(parse::parse_start_time("+3000000000h", now).is_err());
+}
+
+#[test]
+fn test_cli_start_with_duration_adds_past_entry() {
+    let fixture = DBTestFixture::new();
+    let mut running = Timer::new("running".to_string(), String::new(), Utc::now());
+    fixture.db.add_timer_to_db(&mut running).unwrap();
+
+    let output = run_cli(
+        &fixture.db,
+        Command::Start {
+            name: "Meeting".to_string(),
+            description: String::new(),
+            duration: Some("2h".to_string()),
+            date: Some("yesterday".to_string()),
+            at: Some("14:00".to_string()),
+        },
+    );
+    assert!(output.starts_with("Added 'Meeting'"));
+
+    let timers = fixture.db.get_timers_from_db().unwrap();
+    assert_eq!(timers.len(), 2);
+    assert_eq!(timers[0].name, "Meeting");
+    assert_eq!(timers[0].duration(Utc::now()), Duration::hours(2));
+    assert!(timers[1].running);
+}
+
+#[test]
+fn test_find_or_create_project() {
+    let fixture = DBTestFixture::new();
+    let db = &fixture.db;
+
+    assert_eq!(db.find_or_create_project("  ").unwrap(), None);
+    let website = db.find_or_create_project("Acme / Website").unwrap();
+    assert!(website.is_some());
+    assert_eq!(
+        db.find_or_create_project(" Acme /Website ").unwrap(),
+        website
+    );
+    let internal = db.find_or_create_project("Internal").unwrap();
+    assert_ne!(internal, website);
+
+    let names: Vec<String> = db
+        .get_projects()
+        .unwrap()
+        .iter()
+        .map(Project::display_name)
+        .collect();
+    // Projects without a client come first
+    assert_eq!(names, vec!["Internal", "Acme / Website"]);
+}
+
+#[test]
+fn test_project_filter_maps_selection() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    for (name, project) in [("a", "Acme / Website"), ("b", ""), ("c", "Acme / Website")] {
+        app.name_input.set(name);
+        app.project_input.set(project);
+        app.add_timer().unwrap();
+    }
+    assert_eq!(app.projects.len(), 1);
+    assert_eq!(app.timers[0].project_id, app.timers[2].project_id);
+    assert_eq!(app.timers[1].project_id, None);
+
+    app.cycle_project_filter();
+    assert_eq!(app.project_filter, Some(app.projects[0].id));
+    assert_eq!(app.visible_timer_indices(), vec![0, 2]);
+    app.selectable_rows = vec![false, true, true];
+    assert_eq!(app.get_timer_index_from_selection(2), Some(2));
+    assert_eq!(app.get_selection_from_timer_index(2), Some(2));
+    assert_eq!(app.get_selection_from_timer_index(1), None);
+
+    app.cycle_project_filter();
+    assert_eq!(app.project_filter, None);
+    assert_eq!(app.visible_timer_indices(), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_cycle_project_input() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    app.db.find_or_create_project("Acme / Website").unwrap();
+    app.db.find_or_create_project("Internal").unwrap();
+    app.projects = app.db.get_projects().unwrap();
+
+    app.cycle_project_input(true);
+    assert_eq!(app.project_input.value(), "Internal");
+    app.cycle_project_input(true);
+    assert_eq!(app.project_input.value(), "Acme / Website");
+    app.cycle_project_input(true);
+    assert_eq!(app.project_input.value(), "");
+    app.cycle_project_input(false);
+    assert_eq!(app.project_input.value(), "Acme / Website");
+}
+
+#[test]
+fn test_parse_tags() {
+    assert_eq!(
+        parse::parse_tags("#Meeting, review  #meeting #"),
+        vec!["meeting", "review"]
+    );
+    assert!(parse::parse_tags("  ").is_empty());
+}
+
+#[test]
+fn test_tags_are_persisted_and_shared() {
+    let fixture = DBTestFixture::new();
+    let db = &fixture.db;
+    let mut first = Timer::new("first".to_string(), String::new(), Utc::now());
+    first.tags = parse::parse_tags("#review #meeting");
+    db.add_timer_to_db(&mut first).unwrap();
+    let mut second = Timer::new("second".to_string(), String::new(), Utc::now());
+    second.tags = parse::parse_tags("#meeting");
+    db.add_timer_to_db(&mut second).unwrap();
+
+    let timers = db.get_timers_from_db().unwrap();
+    assert_eq!(timers[0].formatted_tags(), "#meeting #review");
+    assert_eq!(timers[1].tags, vec!["meeting"]);
+
+    first.tags = parse::parse_tags("#planning");
+    db.edit_timer(&mut first, "first", "").unwrap();
+    db.delete_timer(second.id, Utc::now()).unwrap();
+    let timers = db.get_timers_from_db().unwrap();
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers[0].tags, vec!["planning"]);
+}
+
+#[test]
+fn test_tag_filter() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    for (name, tags) in [
+        ("a", "#review"),
+        ("b", "#meeting"),
+        ("c", "#meeting #review"),
+    ] {
+        app.name_input.set(name);
+        app.tags_input.set(tags);
+        app.add_timer().unwrap();
+    }
+    assert_eq!(app.all_tags(), vec!["meeting", "review"]);
+
+    app.cycle_tag_filter();
+    assert_eq!(app.tag_filter.as_deref(), Some("meeting"));
+    assert_eq!(app.visible_timer_indices(), vec![1, 2]);
+    app.cycle_tag_filter();
+    assert_eq!(app.visible_timer_indices(), vec![0, 2]);
+    app.cycle_tag_filter();
+    assert_eq!(app.tag_filter, None);
+    assert_eq!(app.visible_timer_indices(), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_stats_totals_from_sql() {
+    let fixture = DBTestFixture::new();
+    let db = &fixture.db;
+    let at = |day: u32, hour: u32| {
+        let time = NaiveDate::from_ymd_opt(2025, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap();
+        Local.from_local_datetime(&time).unwrap().to_utc()
+    };
+    for (name, start, hours) in [
+        ("Work", at(20, 9), 2),
+        ("Work", at(22, 9), 1),
+        ("Review", at(22, 14), 3),
+        ("Old", at(1, 9), 5),
+    ] {
+        let mut timer = Timer::stopped(
+            name.to_string(),
+            String::new(),
+            start,
+            Duration::hours(hours),
+        );
+        db.add_timer_to_db(&mut timer).unwrap();
+    }
+    let mut running = Timer::stopped(
+        "Work".to_string(),
+        String::new(),
+        at(26, 8),
+        Duration::zero(),
+    );
+    running.sessions[0].ended_at = None;
+    db.add_timer_to_db(&mut running).unwrap();
+    let now = at(26, 10);
+
+    let since = NaiveDate::from_ymd_opt(2025, 1, 20).unwrap();
+    assert_eq!(
+        db.daily_totals(since, now).unwrap(),
+        vec![
+            (since, Duration::hours(2)),
+            (
+                NaiveDate::from_ymd_opt(2025, 1, 22).unwrap(),
+                Duration::hours(4)
+            ),
+            (
+                NaiveDate::from_ymd_opt(2025, 1, 26).unwrap(),
+                Duration::hours(2)
+            ),
+        ]
+    );
+    assert_eq!(
+        db.totals_by_name(since, now).unwrap(),
+        vec![
+            ("Work".to_string(), Duration::hours(5)),
+            ("Review".to_string(), Duration::hours(3)),
+        ]
+    );
+
+    // 2025-01-20 is a Monday, the 26th is the Sunday of the same week
+    let stats = Stats::load(db, StatsRange::Week, now).unwrap();
+    assert_eq!(stats.daily.len(), 7);
+    assert_eq!(stats.daily[0], (since, Duration::hours(2)));
+    assert_eq!(stats.daily[1].1, Duration::zero());
+    assert_eq!(stats.weekly, vec![(since, Duration::hours(8))]);
+    assert_eq!(stats.total(), Duration::hours(8));
+
+    let stats = Stats::load(db, StatsRange::Month, now).unwrap();
+    assert_eq!(stats.daily.len(), 30);
+    assert_eq!(stats.weekly.len(), 5);
+    assert_eq!(stats.total(), Duration::hours(13));
+}
+
+#[test]
+fn test_stats_count_local_days() {
+    let fixture = DBTestFixture::new();
+    let db = &fixture.db;
+    let day = NaiveDate::from_ymd_opt(2025, 1, 20).unwrap();
+    let at = |hour, minute| {
+        let time = day.and_hms_opt(hour, minute, 0).unwrap();
+        Local.from_local_datetime(&time).unwrap().to_utc()
+    };
+    // In any zone but UTC one of these starts on another UTC day
+    for (name, start) in [("Early", at(0, 30)), ("Late", at(23, 0))] {
+        let mut timer = Timer::stopped(
+            name.to_string(),
+            String::new(),
+            start,
+            Duration::minutes(30),
+        );
+        db.add_timer_to_db(&mut timer).unwrap();
+    }
+
+    let now = at(23, 45);
+    assert_eq!(
+        db.daily_totals(day, now).unwrap(),
+        vec![(day, Duration::hours(1))]
+    );
+    let stats = Stats::load(db, StatsRange::Week, now).unwrap();
+    assert_eq!(stats.daily.last(), Some(&(day, Duration::hours(1))));
+    assert_eq!(stats.total(), Duration::hours(1));
+    // The 20th is a Monday
+    assert_eq!(stats.weekly.last(), Some(&(day, Duration::hours(1))));
+}
+
+#[test]
+fn test_format_duration_and_daily_target_flag() {
+    assert_eq!(
+        format_duration(Duration::hours(26) + Duration::seconds(61)),
+        "26:01:01"
+    );
+
+    let cli = Cli::try_parse_from(["time-rs", "--daily-target", "7h30m"]).unwrap();
+    assert_eq!(cli.daily_target, Some(Duration::minutes(450)));
+    assert!(Cli::try_parse_from(["time-rs", "--daily-target", "soon"]).is_err());
+}
+
+#[test]
+fn test_config_from_toml() {
+    assert_eq!(Config::from_toml("").unwrap(), Config::default());
+
+    let config = Config::from_toml(
+        r##"
+        date_format = "%Y-%m-%d"
+        allow_multiple_running = true
+        daily_target = "7h30m"
+        delete_confirmation = "dialog"
+
+        [theme]
+        date_row_bg = "#336699"
+        tag_colors = ["red"]
+        "##,
+    )
+    .unwrap();
+    assert_eq!(config.date_format, "%Y-%m-%d");
+    assert!(config.allow_multiple_running);
+    assert_eq!(config.daily_target, Some(Duration::minutes(450)));
+    assert_eq!(config.delete_confirmation, DeleteConfirmation::Dialog);
+    assert_eq!(config.theme.date_row_bg, Color::Rgb(0x33, 0x66, 0x99));
+    assert_eq!(config.theme.tag_colors, vec![Color::Red]);
+    assert_eq!(config.theme.error, Theme::default().error);
+    assert_eq!(config.delete_window_ms, 500);
+
+    assert!(Config::from_toml("daily_target = \"soon\"").is_err());
+    assert!(Config::from_toml("date_format = \"%Q\"").is_err());
+    assert!(Config::from_toml("tick_rate_ms = 0").is_err());
+    assert_eq!(
+        Config::from_toml("tick_rate_ms = 1").unwrap().tick_rate_ms,
+        1
+    );
+    assert!(Config::from_toml("unknown = 1").is_err());
+}
+
+#[test]
+fn test_config_load_and_open_db() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("config.toml");
+    assert!(Config::load(Some(&path)).is_err());
+
+    let db_path = temp_dir.path().join("nested").join("timers.db");
+    std::fs::write(
+        &path,
+        format!(
+            "database_path = {:?}\nallow_multiple_running = true\n",
+            db_path.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    let config = Config::load(Some(&path)).unwrap();
+    let mut app = App::new(config).unwrap();
+    assert!(db_path.exists());
+
+    add_timers(&mut app, &["first", "second"]);
+    assert_eq!(running_names(&app), vec!["first", "second"]);
+}
+
+#[test]
+fn test_begin_delete_requires_timer_selection() {
+    let mut fixture = AppTestFixture::new();
+    let app = &mut fixture.app;
+    add_timers(app, &["first"]);
+
+    app.state.select(Some(0));
+    app.begin_delete();
+    assert!(matches!(app.current_screen, CurrentScreen::Main));
+
+    app.state.select(Some(1));
+    app.begin_delete();
+    assert!(matches!(app.current_screen, CurrentScreen::ConfirmDelete));
+}
+
+#[test]
+fn test_key_binding_parse_and_display() {
+    let binding: KeyBinding = "alt+i".parse().unwrap();
+    assert_eq!(binding.code, KeyCode::Char('i'));
+    assert_eq!(binding.modifiers, KeyModifiers::ALT);
+    assert_eq!(binding.to_string(), "Alt+i");
+
+    let binding: KeyBinding = "Ctrl++".parse().unwrap();
+    assert_eq!(binding.code, KeyCode::Char('+'));
+    assert_eq!(binding.modifiers, KeyModifiers::CONTROL);
+    assert_eq!("space".parse::<KeyBinding>().unwrap().to_string(), "Space");
+    assert_eq!("f5".parse::<KeyBinding>().unwrap().code, KeyCode::F(5));
+    assert_eq!("shift+g".parse::<KeyBinding>().unwrap().to_string(), "G");
+
+    assert!("hyper+x".parse::<KeyBinding>().is_err());
+    assert!("nope".parse::<KeyBinding>().is_err());
+}
+
+#[test]
+fn test_keymap_defaults_and_overrides() {
+    let key = |code, modifiers| KeyEvent::new(code, modifiers);
+    let keymap = Keymap::default();
+    assert_eq!(
+        keymap.action(
+            KeyContext::Main,
+            &key(KeyCode::Char('i'), KeyModifiers::ALT)
+        ),
+        Some(Action::AddTimer)
+    );
+    assert_eq!(
+        keymap.action(
+            KeyContext::Dialog,
+            &key(KeyCode::Char('i'), KeyModifiers::NONE)
+        ),
+        None
+    );
+
+    let config = Config::from_toml(
+        r#"
+        [keys.main]
+        add-timer = ["a", "shift+insert-is-not-a-key"]
+        "#,
+    );
+    assert!(config.is_err());
+
+    let config = Config::from_toml(
+        r#"
+        [keys.main]
+        add-timer = ["a", "ctrl+n"]
+        quit = "q"
+
+        [keys.exit]
+        deny = "esc"
+        "#,
+    )
+    .unwrap();
+    let keys = &config.keys;
+    assert_eq!(
+        keys.action(
+            KeyContext::Main,
+            &key(KeyCode::Char('a'), KeyModifiers::NONE)
+        ),
+        Some(Action::AddTimer)
+    );
+    // Shift is part of upper case letters
+    assert_eq!(
+        keys.action(
+            KeyContext::Main,
+            &key(KeyCode::Char('Q'), KeyModifiers::SHIFT)
+        ),
+        None
+    );
+    assert_eq!(
+        keys.action(
+            KeyContext::Main,
+            &key(KeyCode::Char('q'), KeyModifiers::NONE)
+        ),
+        Some(Action::Quit)
+    );
+    assert_eq!(
+        keys.action(
+            KeyContext::Main,
+            &key(KeyCode::Char('i'), KeyModifiers::ALT)
+        ),
+        None
+    );
+    assert_eq!(
+        keys.action(KeyContext::Exit, &key(KeyCode::Esc, KeyModifiers::NONE)),
+        Some(Action::Deny)
+    );
+    assert_eq!(
+        keys.key(KeyContext::Main, Action::ToggleTimer)
+            .unwrap()
+            .to_string(),
+        "Space"
+    );
+
+    // Actions can only be bound where they exist
+    assert!(Config::from_toml("[keys.exit]\nadd-timer = \"a\"").is_err());
+    assert!(Config::from_toml("[keys.main]\nfly = \"a\"").is_err());
+
+    // A key can only trigger one action per context
+    let error = Config::from_toml("[keys.main]\nadd-timer = \"d\"").unwrap_err();
+    assert!(error.to_string().contains("'d'"), "{}", error);
+    assert!(Config::from_toml("[keys.exit]\ndeny = \"y\"").is_err());
+    // Swapping keys is fine, as is reusing a key in another context
+    let swapped = Config::from_toml(
+        r#"
+        [keys.main]
+        delete-timer = "e"
+        edit-timer = "d"
+
+        [keys.trash]
+        purge-timer = "d"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        swapped.keys.action(
+            KeyContext::Main,
+            &key(KeyCode::Char('d'), KeyModifiers::NONE)
+        ),
+        Some(Action::EditTimer)
+    );
+
+    // Dialog keys must leave characters free to be typed
+    assert!(Config::from_toml("[keys.dialog]\ncancel = \"q\"").is_err());
+    assert!(Config::from_toml("[keys.dialog]\ncancel = \"space\"").is_err());
+    assert!(Config::from_toml("[keys.dialog]\ncancel = [\"esc\", \"ctrl+g\"]").is_ok());
+
+    let mut keymap = Keymap::default();
+    let n = "n".parse::<KeyBinding>().unwrap();
+    assert!(
+        keymap
+            .rebind(KeyContext::Main, Action::Search, vec![n])
+            .is_err()
+    );
+    assert_eq!(keymap, Keymap::default());
+}
+
+/// Feed key presses to the app, letting `gap` pass before each one
+fn press_keys(fixture: &mut AppTestFixture, keys: &[KeyCode], gap: Duration) {
+    for code in keys {
+        fixture.clock.advance(gap);
+        let key = KeyEvent::new(*code, KeyModifiers::NONE);
+        fixture.app.handle_key(&key);
+    }
+}
+
+#[test]
+fn test_double_press_deletes_within_window() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["first", "second"]);
+    fixture.app.state.select(Some(1));
+    let window = Duration::milliseconds(fixture.app.config.delete_window_ms as i64);
+
+    press_keys(&mut fixture, &[KeyCode::Char('d')], window);
+    assert_eq!(fixture.app.timers.len(), 2);
+    press_keys(&mut fixture, &[KeyCode::Char('d')], window);
+    assert_eq!(
+        fixture.app.timers.len(),
+        2,
+        "second press outside the window"
+    );
+
+    // A key in between resets the first press
+    press_keys(
+        &mut fixture,
+        &[KeyCode::Char('d'), KeyCode::Char('j'), KeyCode::Char('d')],
+        window / 10,
+    );
+    assert_eq!(fixture.app.timers.len(), 2);
+
+    press_keys(
+        &mut fixture,
+        &[KeyCode::Char('l'), KeyCode::Char('d'), KeyCode::Char('d')],
+        window / 2,
+    );
+    assert_eq!(fixture.app.timers.len(), 1);
+    assert_eq!(fixture.app.db.get_timers_from_db().unwrap().len(), 1);
+}
+
+#[test]
+fn test_delete_confirmation_dialog_by_keys() {
+    let mut fixture = AppTestFixture::new();
+    fixture.app.config.delete_confirmation = DeleteConfirmation::Dialog;
+    add_timers(&mut fixture.app, &["first"]);
+    fixture.app.state.select(Some(1));
+    let gap = Duration::milliseconds(10);
+
+    press_keys(&mut fixture, &[KeyCode::Char('d'), KeyCode::Char('n')], gap);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Main));
+    assert_eq!(fixture.app.timers.len(), 1);
+
+    press_keys(&mut fixture, &[KeyCode::Char('d'), KeyCode::Enter], gap);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Main));
+    assert!(fixture.app.timers.is_empty());
+}
+
+#[test]
+fn test_add_timer_and_quit_by_keys() {
+    let mut fixture = AppTestFixture::new();
+    let gap = Duration::milliseconds(10);
+
+    fixture
+        .app
+        .handle_key(&KeyEvent::new(KeyCode::Char('i'), KeyModifiers::ALT));
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Add));
+    let mut keys: Vec<KeyCode> = "Write".chars().map(KeyCode::Char).collect();
+    keys.extend([KeyCode::Char('x'), KeyCode::Backspace, KeyCode::Tab]);
+    keys.extend("docs".chars().map(KeyCode::Char));
+    keys.extend([KeyCode::Tab, KeyCode::Tab]);
+    keys.extend("Rust".chars().map(KeyCode::Char));
+    keys.push(KeyCode::Enter);
+    press_keys(&mut fixture, &keys, gap);
+
+    let app = &mut fixture.app;
+    assert!(matches!(app.current_screen, CurrentScreen::Main));
+    assert_eq!(app.timers.len(), 1);
+    assert_eq!(app.timers[0].name, "Write");
+    assert_eq!(app.timers[0].description, "docs");
+    assert_eq!(app.timers[0].tags, vec!["rust"]);
+    assert_eq!(
+        app.action_for_key(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+        Some(Action::Quit)
+    );
+
+    press_keys(&mut fixture, &[KeyCode::Esc, KeyCode::Char('n')], gap);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Main));
+    assert!(!fixture.app.should_quit);
+    press_keys(&mut fixture, &[KeyCode::Esc, KeyCode::Char('y')], gap);
+    assert!(fixture.app.should_quit);
+}
+
+#[test]
+fn test_fake_clock_across_midnight() {
+    let mut fixture = AppTestFixture::new();
+    let evening = NaiveDate::from_ymd_opt(2025, 3, 3)
+        .unwrap()
+        .and_hms_opt(23, 30, 0)
+        .unwrap();
+    let evening = Local.from_local_datetime(&evening).unwrap().to_utc();
+    fixture.clock.set(evening);
+    let app = &mut fixture.app;
+    add_timers(app, &["late"]);
+
+    fixture.clock.advance(Duration::hours(1));
+    assert_eq!(app.timers[0].duration(app.clock.now()), Duration::hours(1));
+    assert_eq!(app.timers[0].formatted_date(), "03-03-2025");
+
+    // The next morning's timer lands in its own date group
+    fixture.clock.advance(Duration::hours(8));
+    app.state.select(Some(1));
+    app.continue_timer().unwrap();
+    assert_eq!(running_names(app), vec!["late"]);
+    assert!(!app.timers[0].running);
+    assert_eq!(app.timers[0].duration(app.clock.now()), Duration::hours(9));
+    assert_eq!(app.timers[1].start_time, evening + Duration::hours(9));
+    assert_eq!(app.get_selection_from_timer_index(1), Some(3));
+
+    fixture.clock.advance(Duration::minutes(45));
+    let timers = app.db.get_timers_from_db().unwrap();
+    let now = app.clock.now();
+    assert_eq!(timers[0].duration(now), Duration::hours(9));
+    assert_eq!(timers[1].formatted_duration(now), "00:45:00");
+}
+
+#[test]
+fn test_cli_with_fake_clock() {
+    let fixture = DBTestFixture::new();
+    let clock = FakeClock::new(Utc::now());
+    let mut out = Vec::new();
+    let start = Command::Start {
+        name: "Focus".to_string(),
+        description: String::new(),
+        duration: None,
+        date: None,
+        at: None,
+    };
+    cli::run(start, &fixture.db, &Config::default(), &clock, &mut out).unwrap();
+
+    clock.advance(Duration::minutes(90));
+    let mut out = Vec::new();
+    cli::run(
+        Command::Status,
+        &fixture.db,
+        &Config::default(),
+        &clock,
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Running 'Focus' for 01:30:00\n"
+    );
+}
+
+#[test]
+fn test_invalid_timestamp_is_an_error() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["first"]);
+    let conn = rusqlite::Connection::open(fixture.temp_dir.path().join("test.db")).unwrap();
+    conn.execute("UPDATE timers SET start_time = 'yesterday'", [])
+        .unwrap();
+
+    assert!(matches!(
+        fixture.app.db.get_timers_from_db(),
+        Err(Error::InvalidTimestamp(timestamp)) if timestamp == "yesterday"
+    ));
+    let error = fixture.app.load().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid timestamp 'yesterday' in the database"
+    );
+}
+
+#[test]
+fn test_database_errors_are_shown_in_status_bar() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["first"]);
+    let conn = rusqlite::Connection::open(fixture.temp_dir.path().join("test.db")).unwrap();
+    conn.execute("DROP TABLE timer_tags", []).unwrap();
+
+    fixture.app.apply(Action::AddTimer);
+    fixture.app.apply(Action::Type('x'));
+    for _ in 0..3 {
+        fixture.app.apply(Action::NextField);
+    }
+    fixture.app.apply(Action::Submit);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Add));
+    assert!(fixture.app.input_error.is_none());
+    assert!(matches!(
+        &fixture.app.status,
+        Some(Status::Error(message)) if message.starts_with("Database error")
+    ));
+
+    // Invalid input stays in the dialog instead
+    fixture.app.apply(Action::Cancel);
+    assert!(fixture.app.status.is_none());
+    fixture.app.begin_add_past();
+    fixture.app.duration_input.set("soon");
+    fixture.app.currently_editing = Some(CurrentlyEditing::Duration);
+    fixture.app.apply(Action::Submit);
+    assert!(fixture.app.input_error.is_some());
+    assert!(fixture.app.status.is_none());
+}
+
+#[test]
+fn test_crash_report_contents() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["secret project"]);
+    let summary = fixture.app.summary();
+    assert!(summary.contains("screen: Main"));
+    assert!(summary.contains("timers: 1 (1 running)"));
+    assert!(!summary.contains("secret"));
+
+    let time = NaiveDate::from_ymd_opt(2025, 3, 3)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+        .and_utc();
+    let report = crash::format_report(
+        "index out of bounds",
+        Some("src/lib/app.rs:10:5"),
+        "0: main",
+        &summary,
+        time,
+    );
+    assert!(report.contains("crashed at 2025-03-03T12:00:00+00:00"));
+    assert!(report.contains("Panic: index out of bounds"));
+    assert!(report.contains("Location: src/lib/app.rs:10:5"));
+    assert!(report.contains(&summary));
+    assert!(report.ends_with("Backtrace:\n0: main\n"));
+}
+
+#[test]
+fn test_offer_crash_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("timers").join("crash.log");
+    crash::write_report(&path, "Panic: boom").unwrap();
+
+    let mut out = Vec::new();
+    crash::offer_report(&path, &mut "y\n".as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Show it now? [y/N]"));
+    assert!(out.contains("Panic: boom"));
+    assert!(!path.exists());
+    let old_path = path.with_extension("log.old");
+    assert_eq!(std::fs::read_to_string(&old_path).unwrap(), "Panic: boom");
+
+    crash::write_report(&path, "Panic: again").unwrap();
+    let mut out = Vec::new();
+    crash::offer_report(&path, &mut "\n".as_bytes(), &mut out).unwrap();
+    assert!(!String::from_utf8(out).unwrap().contains("Panic: again"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_delete_moves_to_trash_and_undo_restores() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["first", "second"]);
+    fixture.app.state.select(Some(1));
+    let gap = Duration::milliseconds(10);
+
+    press_keys(&mut fixture, &[KeyCode::Char('d'), KeyCode::Char('d')], gap);
+    assert_eq!(fixture.app.timers.len(), 1);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info(
+            "Moved 'first' to the trash, u to undo".to_string()
+        ))
+    );
+    let trash = fixture.app.db.get_deleted_timers().unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].deleted_at, Some(fixture.clock.now()));
+
+    press_keys(&mut fixture, &[KeyCode::Char('u')], gap);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Undo: Delete timer".to_string()))
+    );
+    assert_eq!(fixture.app.timers.len(), 2);
+    assert_eq!(fixture.app.state.selected(), Some(1));
+    assert!(fixture.app.db.get_deleted_timers().unwrap().is_empty());
+
+    // Undo still works after moving on
+    press_keys(
+        &mut fixture,
+        &[KeyCode::Char('d'), KeyCode::Char('d'), KeyCode::Char('j')],
+        gap,
+    );
+    press_keys(&mut fixture, &[KeyCode::Char('u')], gap);
+    assert_eq!(fixture.app.timers.len(), 2);
+}
+
+fn press_ctrl(fixture: &mut AppTestFixture, c: char) {
+    let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+    fixture.app.handle_key(&key);
+}
+
+/// The timers as stored, to check undo and redo reach the database
+fn stored(app: &App) -> Vec<(String, i64, bool)> {
+    let now = app.clock.now();
+    app.db
+        .get_timers_from_db()
+        .unwrap()
+        .iter()
+        .map(|timer| {
+            let duration = timer.duration(now).num_minutes();
+            (timer.name.clone(), duration, timer.running)
+        })
+        .collect()
+}
+
+#[test]
+fn test_undo_and_redo_timer_changes() {
+    let mut fixture = AppTestFixture::new();
+    let gap = Duration::minutes(10);
+
+    // Add a timer through the dialog, it starts running
+    fixture.app.apply(Action::AddTimer);
+    fixture.app.name_input.set("write");
+    for _ in 0..4 {
+        fixture.app.apply(Action::Submit);
+    }
+    fixture.app.selectable_rows = vec![false, true];
+    fixture.app.state.select(Some(1));
+    let added = stored(&fixture.app);
+    assert_eq!(added, vec![("write".to_string(), 0, true)]);
+
+    // Stop it, then rename it and change its duration
+    fixture.clock.advance(gap);
+    press_keys(&mut fixture, &[KeyCode::Char(' ')], Duration::zero());
+    let stopped = stored(&fixture.app);
+    assert_eq!(stopped, vec![("write".to_string(), 10, false)]);
+
+    fixture.app.apply(Action::EditTimer);
+    fixture.app.name_input.set("review");
+    fixture.app.duration_input.set("5m");
+    for _ in 0..6 {
+        fixture.app.apply(Action::Submit);
+    }
+    let edited = stored(&fixture.app);
+    assert_eq!(edited, vec![("review".to_string(), 5, false)]);
+
+    // Delete it
+    press_keys(
+        &mut fixture,
+        &[KeyCode::Char('d'), KeyCode::Char('d')],
+        Duration::zero(),
+    );
+    assert!(stored(&fixture.app).is_empty());
+
+    // Walk all the way back
+    press_keys(&mut fixture, &[KeyCode::Char('u')], Duration::zero());
+    assert_eq!(stored(&fixture.app), edited);
+    assert!(fixture.app.db.get_deleted_timers().unwrap().is_empty());
+    press_keys(&mut fixture, &[KeyCode::Char('u')], Duration::zero());
+    assert_eq!(stored(&fixture.app), stopped);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Undo: Edit timer".to_string()))
+    );
+    press_keys(&mut fixture, &[KeyCode::Char('u')], Duration::zero());
+    assert_eq!(fixture.app.timers[0].name, "write");
+    assert!(fixture.app.timers[0].running);
+    press_keys(&mut fixture, &[KeyCode::Char('u')], Duration::zero());
+    assert!(stored(&fixture.app).is_empty());
+    assert!(fixture.app.db.get_deleted_timers().unwrap().is_empty());
+    press_keys(&mut fixture, &[KeyCode::Char('u')], Duration::zero());
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Nothing to undo".to_string()))
+    );
+
+    // And forward again, the timer keeps its id
+    press_ctrl(&mut fixture, 'r');
+    assert_eq!(fixture.app.timers.len(), 1);
+    press_ctrl(&mut fixture, 'r');
+    press_ctrl(&mut fixture, 'r');
+    assert_eq!(stored(&fixture.app), edited);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Redo: Edit timer".to_string()))
+    );
+
+    // A new change drops what could be redone
+    press_keys(&mut fixture, &[KeyCode::Char('u')], Duration::zero());
+    fixture.app.apply(Action::ContinueTimer);
+    press_ctrl(&mut fixture, 'r');
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Nothing to redo".to_string()))
+    );
+    assert_eq!(running_names(&fixture.app), vec!["write"]);
+}
+
+#[test]
+fn test_trash_restore_and_purge() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["first", "second", "third"]);
+    let gap = Duration::minutes(1);
+    for _ in 0..2 {
+        fixture.app.state.select(Some(1));
+        fixture.app.apply(Action::DeleteTimer);
+        fixture.clock.advance(gap);
+    }
+    let stats = Stats::load(&fixture.app.db, StatsRange::Week, fixture.clock.now()).unwrap();
+    assert_eq!(stats.by_name.len(), 1);
+
+    press_keys(&mut fixture, &[KeyCode::Char('T')], gap);
+    let app = &fixture.app;
+    assert!(matches!(app.current_screen, CurrentScreen::Trash));
+    let names: Vec<&str> = app.trash.iter().map(|timer| timer.name.as_str()).collect();
+    assert_eq!(names, vec!["second", "first"]);
+    assert_eq!(app.trash_state.selected(), Some(0));
+
+    // Restore "first", then purge "second"
+    press_keys(&mut fixture, &[KeyCode::Down, KeyCode::Char('r')], gap);
+    assert_eq!(fixture.app.trash.len(), 1);
+    assert_eq!(fixture.app.trash_state.selected(), Some(0));
+    assert_eq!(fixture.app.timers.len(), 2);
+    press_keys(&mut fixture, &[KeyCode::Char('x')], gap);
+    assert!(fixture.app.trash.is_empty());
+    assert_eq!(fixture.app.trash_state.selected(), None);
+    press_keys(&mut fixture, &[KeyCode::Esc], gap);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Main));
+
+    let db = &fixture.app.db;
+    assert!(db.get_deleted_timers().unwrap().is_empty());
+    let names: Vec<String> = db
+        .get_timers_from_db()
+        .unwrap()
+        .into_iter()
+        .map(|timer| timer.name)
+        .collect();
+    assert_eq!(names, vec!["first", "third"]);
+
+    // Deleting for good can't be undone from the main screen
+    press_keys(
+        &mut fixture,
+        &[KeyCode::Char('u'), KeyCode::Char('u')],
+        Duration::zero(),
+    );
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Nothing to undo".to_string()))
+    );
+    let names: Vec<String> = fixture
+        .app
+        .db
+        .get_timers_from_db()
+        .unwrap()
+        .into_iter()
+        .map(|timer| timer.name)
+        .collect();
+    assert_eq!(names, vec!["first", "third"]);
+    assert!(fixture.app.db.get_deleted_timers().unwrap().is_empty());
+}
+
+#[test]
+fn test_trash_retention() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["old", "recent", "kept"]);
+    let now = fixture.clock.now();
+    let db = &fixture.app.db;
+    let ids: Vec<usize> = fixture.app.timers.iter().map(|timer| timer.id).collect();
+    db.delete_timer(ids[0], now - Duration::days(31)).unwrap();
+    db.delete_timer(ids[1], now - Duration::days(29)).unwrap();
+
+    fixture.app.config.trash_retention_days = 0;
+    fixture.app.load().unwrap();
+    assert_eq!(fixture.app.db.get_deleted_timers().unwrap().len(), 2);
+
+    fixture.app.config.trash_retention_days = 30;
+    fixture.app.load().unwrap();
+    let trash = fixture.app.db.get_deleted_timers().unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].name, "recent");
+    assert_eq!(fixture.app.timers.len(), 1);
+}
+
+#[test]
+fn test_text_input_editing() {
+    let mut input = TextInput::default();
+    input.set("cafe\u{301} 👍🏽 time");
+    input.move_end();
+    input.move_word_left();
+    input.move_left();
+    input.move_left();
+    assert_eq!(&input.value()[input.cursor()..], "👍🏽 time");
+
+    // Combined characters are deleted as a whole
+    input.delete_after();
+    assert_eq!(input.value(), "cafe\u{301}  time");
+    input.delete_before();
+    input.delete_before();
+    assert_eq!(input.value(), "caf time");
+
+    input.move_home();
+    input.insert('x');
+    input.move_word_right();
+    input.insert_str("line\none\ttab\u{7}");
+    assert_eq!(input.value(), "xcafline one tab time");
+
+    input.delete_word_before();
+    assert_eq!(input.value(), "xcafline one  time");
+    input.delete_word_before();
+    assert_eq!(input.value(), "xcafline  time");
+    input.move_end();
+    input.move_word_left();
+    input.delete_to_start();
+    assert_eq!(input.value(), "time");
+    assert_eq!(input.cursor(), 0);
+}
+
+#[test]
+fn test_input_field_scrolls_to_cursor() {
+    let area = Rect::new(0, 0, 5, 1);
+    let mut buffer = Buffer::empty(area);
+    let mut input = TextInput::default();
+    input.set("abcdefgh");
+    InputField::default().render(area, &mut buffer, &mut input);
+    assert_eq!(buffer, Buffer::with_lines(["efgh "]));
+    assert_eq!(input.cursor_position(area), Position::new(4, 0));
+
+    // Moving left keeps the view until the cursor leaves it
+    for _ in 0..5 {
+        input.move_left();
+    }
+    InputField::default().render(area, &mut buffer, &mut input);
+    assert_eq!(buffer, Buffer::with_lines(["defgh"]));
+    assert_eq!(input.cursor_position(area), Position::new(0, 0));
+}
+
+#[test]
+fn test_dialog_keys_and_paste_edit_the_field() {
+    let mut fixture = AppTestFixture::new();
+    fixture.app.apply(Action::AddTimer);
+    fixture.app.paste("review\n");
+    let gap = Duration::milliseconds(10);
+    press_keys(
+        &mut fixture,
+        &[KeyCode::Home, KeyCode::Char('c'), KeyCode::Right],
+        gap,
+    );
+    assert_eq!(fixture.app.name_input.value(), "creview ");
+
+    // Shortcuts with Ctrl aren't typed into the field
+    for c in ['e', 'w'] {
+        let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        fixture.app.handle_key(&key);
+    }
+    assert_eq!(fixture.app.name_input.value(), "");
+
+    // Pastes outside of a dialog are ignored
+    fixture.app.apply(Action::Cancel);
+    fixture.app.paste("ignored");
+    fixture.app.apply(Action::AddTimer);
+    assert_eq!(fixture.app.name_input.value(), "");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_edit_text_in_external_editor() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("editor.sh");
+    std::fs::write(&script, "#!/bin/sh\nprintf '\\nsecond line\\n' >> \"$1\"\n").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let edited = editor::edit(script.to_str().unwrap(), "first line").unwrap();
+    assert_eq!(edited, "first line\nsecond line");
+
+    // The file has a random name, is private to the user and is removed
+    let script = temp_dir.path().join("inspect.sh");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\nprintf '%s %s' \"$(ls -l \"$1\" | cut -c1-10)\" \"$1\" > \"$1\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let edited = editor::edit(script.to_str().unwrap(), "private").unwrap();
+    let (mode, path) = edited.split_once(' ').unwrap();
+    assert_eq!(mode, "-rw-------");
+    let name = std::path::Path::new(path)
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(
+        name.starts_with("time-rs-") && name.ends_with(".txt"),
+        "{}",
+        name
+    );
+    assert_ne!(name, format!("time-rs-{}.txt", std::process::id()));
+    assert!(!std::path::Path::new(path).exists());
+
+    let result = editor::edit("false", "unchanged");
+    assert!(matches!(result, Err(Error::Editor(_))));
+    let result = editor::edit("time-rs-no-such-editor", "unchanged");
+    assert!(matches!(result, Err(Error::Editor(_))));
+}
+
+#[test]
+fn test_set_description_from_editor() {
+    let mut fixture = AppTestFixture::new();
+    add_timers(&mut fixture.app, &["meeting"]);
+    fixture.app.state.select(Some(1));
+
+    press_keys(&mut fixture, &[KeyCode::Char('E')], Duration::zero());
+    let id = fixture.app.pending_editor.take().unwrap();
+    assert_eq!(id, fixture.app.timers[0].id);
+
+    let notes = "Agenda\n- budget\n- hiring".to_string();
+    fixture.app.set_description(id, notes.clone()).unwrap();
+    assert_eq!(fixture.app.selected_timer().unwrap().description, notes);
+    let stored = fixture.app.db.get_timer(id).unwrap().unwrap();
+    assert_eq!(stored.description, notes);
+
+    fixture.app.apply(Action::Undo);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("Undo: Edit notes".to_string()))
+    );
+    assert_eq!(fixture.app.timers[0].description, "");
+}
+
+#[test]
+fn test_search_queries() {
+    let mut timer = Timer::new(
+        "Code review".to_string(),
+        "PR (#42)".to_string(),
+        Utc::now(),
+    );
+    let search = |query: &str| Search::new(query).unwrap();
+
+    assert!(Search::new("").is_none());
+    assert!(search("review").is_match(&timer));
+    assert!(search("^code").is_match(&timer));
+    assert!(!search("^review").is_match(&timer));
+    // Upper case letters make the search case sensitive
+    assert!(!search("Review").is_match(&timer));
+    // Invalid regular expressions are searched for literally
+    assert!(search("(#42").is_match(&timer));
+    assert_eq!(
+        search("e").match_ranges("Code review"),
+        vec![3..4, 6..7, 9..10]
+    );
+
+    timer.name = "Lunch".to_string();
+    assert!(!search("review").is_match(&timer));
+}
+
+#[test]
+fn test_search_filters_rows_and_jumps_between_matches() {
+    let mut fixture = AppTestFixture::new();
+    let day = |day: u32| {
+        NaiveDate::from_ymd_opt(2025, 3, day)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc()
+    };
+    for (name, description, start) in [
+        ("Code review", "", day(3)),
+        ("Lunch", "", day(3)),
+        ("Planning", "weekly review", day(4)),
+        ("Lunch", "", day(4)),
+    ] {
+        let mut timer = Timer::stopped(
+            name.to_string(),
+            description.to_string(),
+            start,
+            Duration::hours(1),
+        );
+        fixture.app.db.add_timer_to_db(&mut timer).unwrap();
+    }
+    fixture.app.load().unwrap();
+    fixture.app.update_selectable_rows();
+    assert_eq!(
+        fixture.app.selectable_rows,
+        vec![false, true, true, false, true, true]
+    );
+    fixture.app.state.select(Some(2));
+
+    let gap = Duration::milliseconds(10);
+    let mut keys = vec![KeyCode::Char('/')];
+    keys.extend("rev".chars().map(KeyCode::Char));
+    press_keys(&mut fixture, &keys, gap);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Search));
+    assert_eq!(fixture.app.selectable_rows, vec![false, true, false, true]);
+    let names = |app: &App| -> Vec<String> {
+        app.visible_timer_indices()
+            .iter()
+            .map(|&index| app.timers[index].name.clone())
+            .collect()
+    };
+    assert_eq!(names(&fixture.app), vec!["Code review", "Planning"]);
+    // The selected timer was hidden, so the first match is selected
+    let selected = |app: &App| app.selected_timer().unwrap().name.clone();
+    assert_eq!(fixture.app.state.selected(), Some(1));
+    assert_eq!(selected(&fixture.app), "Code review");
+
+    // n and N wrap around the matches, skipping the date rows
+    press_keys(&mut fixture, &[KeyCode::Enter, KeyCode::Char('n')], gap);
+    assert!(matches!(fixture.app.current_screen, CurrentScreen::Main));
+    assert_eq!(fixture.app.state.selected(), Some(3));
+    assert_eq!(selected(&fixture.app), "Planning");
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("/rev - match 2 of 2".to_string()))
+    );
+    press_keys(&mut fixture, &[KeyCode::Char('n')], gap);
+    assert_eq!(selected(&fixture.app), "Code review");
+    press_keys(&mut fixture, &[KeyCode::Char('N')], gap);
+    assert_eq!(selected(&fixture.app), "Planning");
+
+    // A search without matches says so
+    let mut keys = vec![KeyCode::Char('/')];
+    keys.extend("xyz".chars().map(KeyCode::Char));
+    keys.push(KeyCode::Enter);
+    press_keys(&mut fixture, &keys, gap);
+    assert!(fixture.app.selectable_rows.is_empty());
+    assert_eq!(fixture.app.state.selected(), None);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("No timers match 'xyz'".to_string()))
+    );
+
+    // Esc in the search bar shows all timers again
+    press_keys(&mut fixture, &[KeyCode::Char('/'), KeyCode::Esc], gap);
+    assert!(fixture.app.search.is_none());
+    assert_eq!(fixture.app.selectable_rows.len(), 6);
+    press_keys(&mut fixture, &[KeyCode::Char('n')], gap);
+    assert_eq!(
+        fixture.app.status,
+        Some(Status::Info("No search, press / to search".to_string()))
+    );
+}